@@ -1,13 +1,29 @@
-#![feature(test)]
+// Benchmarks that run natively with `cargo bench`, without the nightly only
+// `test` crate.
 
-extern crate test;
 extern crate wasm_game_of_life;
 
-#[bench]
-fn universe_ticks(b: &mut test::Bencher) {
-    let mut universe = wasm_game_of_life::Universe::new();
+use std::time::{Duration, Instant};
 
-    b.iter(|| {
-        universe.tick();
-    });
+use wasm_game_of_life::{PackedUniverse, Universe};
+
+// Run `f` repeatedly for about a second and print the average time per run
+fn bench<F: FnMut()>(name: &str, mut f: F) {
+    let started = Instant::now();
+    let mut runs = 0;
+    while started.elapsed() < Duration::from_secs(1) {
+        f();
+        runs += 1;
+    }
+    println!("{:<24} {:>12?}/iter ({} runs)", name, started.elapsed() / runs, runs);
+}
+
+fn main() {
+    let mut universe = Universe::new();
+    universe.randomize_with_seed(1, 0.5);
+    bench("universe_ticks", || universe.tick());
+
+    let mut packed = PackedUniverse::new(2048, 2048);
+    packed.randomize_with_seed(1, 0.5);
+    bench("packed_universe_ticks", || packed.tick());
 }
@@ -0,0 +1,52 @@
+// How long each cell has been alive and how often it changed, for heat maps.
+//
+// Both buffers run parallel to the cells. The age of a live cell is the
+// number of ticks it has survived, 0 for a cell just born, and is 0 for every
+// other cell. The activity of a cell counts every change of its state since
+// tracking started or the universe was last reset. Both saturate rather than
+// wrap, a cell that is old enough is simply old.
+
+pub struct CellAges {
+    ages: Vec<u16>,
+    activity: Vec<u32>,
+}
+
+impl CellAges {
+    /// Start tracking `len` cells, taking every live cell as newborn.
+    pub fn new(len: usize) -> CellAges {
+        CellAges {
+            ages: vec![0; len],
+            activity: vec![0; len],
+        }
+    }
+
+    pub fn ages(&self) -> &[u16] {
+        &self.ages
+    }
+
+    pub fn activity(&self) -> &[u32] {
+        &self.activity
+    }
+
+    /// Age every cell that is alive in `alive`, ahead of a tick. Cells that
+    /// die or are born in the tick are then reset by `changed`.
+    pub fn grow(&mut self, alive: impl Iterator<Item = bool>) {
+        for (age, alive) in self.ages.iter_mut().zip(alive) {
+            if alive {
+                *age = age.saturating_add(1);
+            }
+        }
+    }
+
+    /// The cell at `index` changed state.
+    pub fn changed(&mut self, index: usize) {
+        self.ages[index] = 0;
+        self.activity[index] = self.activity[index].saturating_add(1);
+    }
+
+    /// Start over with every cell newborn and nothing changed yet.
+    pub fn clear(&mut self) {
+        self.ages.iter_mut().for_each(|age| *age = 0);
+        self.activity.iter_mut().for_each(|count| *count = 0);
+    }
+}
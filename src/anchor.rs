@@ -0,0 +1,50 @@
+// Where to keep the existing cells when the universe is resized.
+//
+// The anchor picks the point of the old grid that stays put relative to the
+// new grid: with `TopLeft` the top left corners line up and the universe grows
+// or shrinks to the right and bottom, with `Center` the middles line up and it
+// grows or shrinks evenly on every side.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// How far the old cells move down and right when a `width` x `height`
+    /// grid becomes a `new_width` x `new_height` one. Negative offsets crop
+    /// the top or left.
+    pub fn offset(self, width: u32, height: u32, new_width: u32, new_height: u32) -> (i64, i64) {
+        // 0 for the start, 1 for the middle and 2 for the end of each axis
+        let (vertical, horizontal) = match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (0, 1),
+            Anchor::TopRight => (0, 2),
+            Anchor::Left => (1, 0),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (1, 2),
+            Anchor::BottomLeft => (2, 0),
+            Anchor::Bottom => (2, 1),
+            Anchor::BottomRight => (2, 2),
+        };
+        let shift =
+            |position: i64, old: u32, new: u32| (i64::from(new) - i64::from(old)) * position / 2;
+        (
+            shift(vertical, height, new_height),
+            shift(horizontal, width, new_width),
+        )
+    }
+}
@@ -0,0 +1,202 @@
+// Headless command line runner for the universe.
+//
+// Loads a pattern file, runs it for a number of generations and writes the
+// result, or prints the population of every generation. Meant for running
+// experiments on machines without a browser.
+
+extern crate wasm_game_of_life;
+
+use std::env;
+use std::fs;
+use std::process;
+
+use wasm_game_of_life::{Anchor, Topology, Universe};
+
+const USAGE: &str = "\
+Usage: life [OPTIONS] <PATTERN>
+
+Runs the pattern in the file PATTERN and writes the final generation. The
+pattern can be RLE, plaintext (.cells), Life 1.05, Life 1.06 or macrocell
+(.mc).
+
+Options:
+  -g, --generations <N>   Number of generations to run [default: 1]
+  -r, --rule <RULE>       Rule to run instead of the pattern's, e.g. B36/S23
+  -t, --topology <NAME>   torus, bounded, cylinder, klein or cross [default: torus]
+  -W, --width <N>         Width of the universe [default: pattern width]
+  -H, --height <N>        Height of the universe [default: pattern height]
+  -f, --format <FORMAT>   Output format, rle, cells, life105, life106, mc or
+                          text [default: rle]
+  -p, --population        Print `generation population` for every generation
+                          instead of the final pattern
+  -o, --output <FILE>     Write the final pattern to FILE instead of stdout
+  -h, --help              Print this message
+";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Rle,
+    Plaintext,
+    Life105,
+    Life106,
+    Macrocell,
+    Text,
+}
+
+struct Options {
+    pattern: String,
+    generations: u64,
+    rule: Option<String>,
+    topology: Topology,
+    width: Option<u32>,
+    height: Option<u32>,
+    format: Format,
+    population: bool,
+    output: Option<String>,
+}
+
+fn parse_topology(name: &str) -> Result<Topology, String> {
+    match name {
+        "torus" => Ok(Topology::Torus),
+        "bounded" => Ok(Topology::Bounded),
+        "cylinder" => Ok(Topology::Cylinder),
+        "klein" => Ok(Topology::KleinBottle),
+        "cross" => Ok(Topology::CrossSurface),
+        _ => Err(format!("unknown topology \"{}\"", name)),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got \"{}\"", option, value))
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        pattern: String::new(),
+        generations: 1,
+        rule: None,
+        topology: Topology::Torus,
+        width: None,
+        height: None,
+        format: Format::Rle,
+        population: false,
+        output: None,
+    };
+    let mut pattern = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let option = arg.as_str();
+        if option == "-h" || option == "--help" {
+            print!("{}", USAGE);
+            process::exit(0);
+        }
+        if option == "-p" || option == "--population" {
+            options.population = true;
+            continue;
+        }
+        if !option.starts_with('-') {
+            if pattern.replace(arg.clone()).is_some() {
+                return Err("only one pattern file can be given".to_string());
+            }
+            continue;
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} expects a value", option))?;
+        match option {
+            "-g" | "--generations" => options.generations = parse_number(option, value)?,
+            "-r" | "--rule" => options.rule = Some(value.clone()),
+            "-t" | "--topology" => options.topology = parse_topology(value)?,
+            "-W" | "--width" => options.width = Some(parse_number(option, value)?),
+            "-H" | "--height" => options.height = Some(parse_number(option, value)?),
+            "-f" | "--format" => {
+                options.format = match value.as_str() {
+                    "rle" => Format::Rle,
+                    "cells" => Format::Plaintext,
+                    "life105" => Format::Life105,
+                    "life106" => Format::Life106,
+                    "mc" => Format::Macrocell,
+                    "text" => Format::Text,
+                    _ => return Err(format!("unknown format \"{}\"", value)),
+                }
+            }
+            "-o" | "--output" => options.output = Some(value.clone()),
+            _ => return Err(format!("unknown option {}", option)),
+        }
+    }
+
+    options.pattern = pattern.ok_or_else(|| "no pattern file given".to_string())?;
+    Ok(options)
+}
+
+// Load the pattern, centered in a universe of the requested size
+fn load(options: &Options) -> Result<Universe, String> {
+    let text = fs::read_to_string(&options.pattern)
+        .map_err(|err| format!("can not read {}: {}", options.pattern, err))?;
+    let mut universe = Universe::from_pattern(&text).map_err(|err| err.to_string())?;
+
+    let width = options.width.unwrap_or_else(|| universe.width());
+    let height = options.height.unwrap_or_else(|| universe.height());
+    universe
+        .resize(width, height, Anchor::Center)
+        .map_err(|err| err.to_string())?;
+    universe.set_topology(options.topology);
+    if let Some(rule) = &options.rule {
+        universe.set_rule(rule).map_err(|err| err.to_string())?;
+    }
+    Ok(universe)
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let mut universe = load(options)?;
+
+    if options.population {
+        println!("0 {}", universe.population());
+        for generation in 1..=options.generations {
+            universe.tick();
+            println!("{} {}", generation, universe.population());
+        }
+        return Ok(());
+    }
+
+    for _ in 0..options.generations {
+        universe.tick();
+    }
+    let result = match options.format {
+        Format::Rle => universe.to_rle(),
+        Format::Plaintext => universe.to_plaintext(),
+        Format::Life105 => universe.to_life105(),
+        Format::Life106 => universe.to_life106(),
+        Format::Macrocell => universe.to_macrocell(),
+        Format::Text => universe.render(),
+    };
+    match &options.output {
+        Some(path) => {
+            fs::write(path, result).map_err(|err| format!("can not write {}: {}", path, err))
+        }
+        None => {
+            print!("{}", result);
+            Ok(())
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("life: {}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(err) = run(&options) {
+        eprintln!("life: {}", err);
+        process::exit(1);
+    }
+}
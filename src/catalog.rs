@@ -0,0 +1,216 @@
+// A built-in library of well known patterns.
+//
+// Every pattern is kept as RLE together with what kind of pattern it is and,
+// for oscillators, spaceships and guns, its period and speed. Names are
+// lowercase with dashes, like `gosper-glider-gun`. The patterns come from
+// https://conwaylife.com/wiki and all run Conway's rule.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{rle, Pattern, RlePattern};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatternKind {
+    /// Never changes.
+    StillLife,
+    /// Comes back to the same state after its period.
+    Oscillator,
+    /// Comes back to the same state after its period, somewhere else.
+    Spaceship,
+    /// Oscillates while shooting out spaceships.
+    Gun,
+    /// A small pattern that takes a long time to settle down.
+    Methuselah,
+}
+
+struct Entry {
+    name: &'static str,
+    kind: PatternKind,
+    period: Option<u32>,
+    // In cells per generation, `c/4` is a cell every four generations
+    speed: Option<&'static str>,
+    rle: &'static str,
+}
+
+const CATALOG: &[Entry] = &[
+    Entry {
+        name: "block",
+        kind: PatternKind::StillLife,
+        period: Some(1),
+        speed: None,
+        rle: "x = 2, y = 2\n2o$2o!",
+    },
+    Entry {
+        name: "beehive",
+        kind: PatternKind::StillLife,
+        period: Some(1),
+        speed: None,
+        rle: "x = 4, y = 3\nb2o$o2bo$b2o!",
+    },
+    Entry {
+        name: "blinker",
+        kind: PatternKind::Oscillator,
+        period: Some(2),
+        speed: None,
+        rle: "x = 3, y = 1\n3o!",
+    },
+    Entry {
+        name: "toad",
+        kind: PatternKind::Oscillator,
+        period: Some(2),
+        speed: None,
+        rle: "x = 4, y = 2\nb3o$3o!",
+    },
+    Entry {
+        name: "beacon",
+        kind: PatternKind::Oscillator,
+        period: Some(2),
+        speed: None,
+        rle: "x = 4, y = 4\n2o$2o$2b2o$2b2o!",
+    },
+    Entry {
+        name: "pulsar",
+        kind: PatternKind::Oscillator,
+        period: Some(3),
+        speed: None,
+        rle: "x = 13, y = 13\n2b3o3b3o2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2$2b3o3b3o$\
+              o4bobo4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!",
+    },
+    Entry {
+        name: "pentadecathlon",
+        kind: PatternKind::Oscillator,
+        period: Some(15),
+        speed: None,
+        rle: "x = 10, y = 3\n2bo4bo$2ob4ob2o$2bo4bo!",
+    },
+    Entry {
+        name: "glider",
+        kind: PatternKind::Spaceship,
+        period: Some(4),
+        speed: Some("c/4"),
+        rle: "x = 3, y = 3\nbo$2bo$3o!",
+    },
+    Entry {
+        name: "lwss",
+        kind: PatternKind::Spaceship,
+        period: Some(4),
+        speed: Some("c/2"),
+        rle: "x = 5, y = 4\nbo2bo$o$o3bo$4o!",
+    },
+    Entry {
+        name: "mwss",
+        kind: PatternKind::Spaceship,
+        period: Some(4),
+        speed: Some("c/2"),
+        rle: "x = 6, y = 5\n3bo$bo3bo$o$o4bo$5o!",
+    },
+    Entry {
+        name: "hwss",
+        kind: PatternKind::Spaceship,
+        period: Some(4),
+        speed: Some("c/2"),
+        rle: "x = 7, y = 5\n3b2o$bo4bo$o$o5bo$6o!",
+    },
+    Entry {
+        name: "gosper-glider-gun",
+        kind: PatternKind::Gun,
+        period: Some(30),
+        speed: None,
+        rle: "x = 36, y = 9\n24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$\
+              2o8bo3bob2o4bobo$10bo5bo7bo$11bo3bo$12b2o!",
+    },
+    Entry {
+        name: "r-pentomino",
+        kind: PatternKind::Methuselah,
+        period: None,
+        speed: None,
+        rle: "x = 3, y = 3\nb2o$2o$bo!",
+    },
+    Entry {
+        name: "acorn",
+        kind: PatternKind::Methuselah,
+        period: None,
+        speed: None,
+        rle: "x = 7, y = 3\nbo$3bo$2o2b3o!",
+    },
+    Entry {
+        name: "diehard",
+        kind: PatternKind::Methuselah,
+        period: None,
+        speed: None,
+        rle: "x = 8, y = 3\n6bo$2o$bo3b3o!",
+    },
+];
+
+/// A pattern from the built-in library.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy)]
+pub struct PatternInfo {
+    entry: &'static Entry,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PatternInfo {
+    pub fn name(&self) -> String {
+        self.entry.name.to_string()
+    }
+
+    pub fn kind(&self) -> PatternKind {
+        self.entry.kind
+    }
+
+    /// Generations until the pattern repeats, `undefined` for patterns that
+    /// do not.
+    pub fn period(&self) -> Option<u32> {
+        self.entry.period
+    }
+
+    /// How fast a spaceship moves, like `c/4`.
+    pub fn speed(&self) -> Option<String> {
+        self.entry.speed.map(str::to_string)
+    }
+
+    /// Width of the bounding box of the pattern.
+    pub fn width(&self) -> u32 {
+        self.pattern().width
+    }
+
+    /// Height of the bounding box of the pattern.
+    pub fn height(&self) -> u32 {
+        self.pattern().height
+    }
+
+    pub fn rle(&self) -> String {
+        self.entry.rle.to_string()
+    }
+
+    /// The pattern, ready to be turned and placed with
+    /// `Universe.set_pattern`.
+    pub fn to_pattern(&self) -> Pattern {
+        Pattern::from(self.pattern())
+    }
+}
+
+impl PatternInfo {
+    /// The decoded pattern.
+    pub fn pattern(&self) -> RlePattern {
+        rle::decode(self.entry.rle).expect("catalog patterns are valid RLE")
+    }
+}
+
+/// Every pattern in the library.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn patterns() -> Vec<PatternInfo> {
+    CATALOG.iter().map(|entry| PatternInfo { entry }).collect()
+}
+
+/// The pattern called `name`, `undefined` if there is none.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn find_pattern(name: &str) -> Option<PatternInfo> {
+    CATALOG
+        .iter()
+        .find(|entry| entry.name == name)
+        .map(|entry| PatternInfo { entry })
+}
@@ -0,0 +1,165 @@
+// Detects when a universe settles into a still life, an oscillator or a
+// spaceship.
+//
+// Every generation is hashed as it is ticked and the hashes are remembered
+// with the generation they were seen in. When a hash comes up again the
+// universe has repeated itself, and the distance between the two generations
+// is the period. To spot spaceships the live cells can be hashed relative to
+// their bounding box instead, so a pattern that moved still hashes the same.
+//
+// Hashing every generation costs about as much as ticking it, so nothing is
+// recorded until detection is turned on.
+//
+// Hashes are 64 bits, a false repeat from a collision is possible but
+// vanishingly unlikely.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::Cell;
+
+// Generations remembered by default
+const DEFAULT_LIMIT: usize = 4096;
+
+/// A repeating state: generation `start + period` is the same as generation
+/// `start`, moved by `dx` columns and `dy` rows. A period of 1 with no
+/// movement is a still life.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: u32,
+    pub period: u32,
+    pub dx: i32,
+    pub dy: i32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Cycle {
+    pub fn is_still_life(&self) -> bool {
+        self.period == 1 && !self.is_spaceship()
+    }
+
+    pub fn is_spaceship(&self) -> bool {
+        self.dx != 0 || self.dy != 0
+    }
+}
+
+pub struct CycleDetector {
+    // Hash of a generation -> the generation and the top left corner of its
+    // live cells
+    seen: HashMap<u64, (u32, i64, i64)>,
+    // Hashes in the order they were seen, to forget the oldest
+    order: VecDeque<u64>,
+    limit: usize,
+    enabled: bool,
+    translations: bool,
+    cycle: Option<Cycle>,
+}
+
+impl CycleDetector {
+    pub fn new() -> CycleDetector {
+        CycleDetector {
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+            limit: DEFAULT_LIMIT,
+            enabled: false,
+            translations: false,
+            cycle: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    /// The cycle found so far, if any.
+    pub fn cycle(&self) -> Option<Cycle> {
+        self.cycle
+    }
+
+    /// Remember the cells of `generation`, which must be the generation
+    /// after the last recorded one.
+    pub fn record(&mut self, generation: u32, width: u32, cells: &[Cell]) {
+        if !self.enabled || self.cycle.is_some() {
+            return;
+        }
+
+        let (hash, top, left) = self.hash(width, cells);
+        if let Some(&(start, start_top, start_left)) = self.seen.get(&hash) {
+            self.cycle = Some(Cycle {
+                start,
+                period: generation - start,
+                dx: (left - start_left) as i32,
+                dy: (top - start_top) as i32,
+            });
+            return;
+        }
+
+        self.seen.insert(hash, (generation, top, left));
+        self.order.push_back(hash);
+        self.shrink();
+    }
+
+    /// Whether to record generations and look for a cycle at all.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.clear();
+    }
+
+    /// Whether to also find patterns that repeat somewhere else.
+    pub fn set_translations(&mut self, translations: bool) {
+        self.translations = translations;
+        self.clear();
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.shrink();
+    }
+
+    pub fn clear(&mut self) {
+        self.seen.clear();
+        self.order.clear();
+        self.cycle = None;
+    }
+
+    // Hash the cells, and find the top left corner of the live and dying
+    // cells when looking for translated repeats
+    fn hash(&self, width: u32, cells: &[Cell]) -> (u64, i64, i64) {
+        let mut hasher = DefaultHasher::new();
+        if !self.translations {
+            for &cell in cells {
+                cell.hash(&mut hasher);
+            }
+            return (hasher.finish(), 0, 0);
+        }
+
+        let width = width.max(1) as usize;
+        let live = || {
+            cells
+                .iter()
+                .enumerate()
+                .filter(|&(_, &cell)| cell != Cell::Dead)
+                .map(|(index, &cell)| ((index / width) as i64, (index % width) as i64, cell))
+        };
+        let top = live().map(|(row, _, _)| row).min().unwrap_or(0);
+        let left = live().map(|(_, col, _)| col).min().unwrap_or(0);
+        for (row, col, cell) in live() {
+            (row - top, col - left, cell).hash(&mut hasher);
+        }
+        (hasher.finish(), top, left)
+    }
+
+    // Forget the oldest generations until the limit is respected
+    fn shrink(&mut self) {
+        while self.order.len() > self.limit {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+    }
+}
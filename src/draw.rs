@@ -0,0 +1,74 @@
+// Shapes for the drawing tools.
+//
+// Lines are Bresenham lines: they step one cell at a time along the longer
+// axis, the major axis, and pick the cell nearest to the ideal line along the
+// other one. That cell has a closed form, the offset along the minor axis
+// after `step` steps is `step * rise / length` rounded half up. So the steps
+// that stay inside the grid are worked out up front and only those are
+// walked, however far outside the grid the line runs. See
+// https://en.wikipedia.org/wiki/Bresenham%27s_line_algorithm
+
+/// The `(row, column)` of every cell on the line from `(row0, column0)` to
+/// `(row1, column1)`, both ends included, that is inside a `width` x
+/// `height` grid.
+pub fn line(
+    row0: u32,
+    column0: u32,
+    row1: u32,
+    column1: u32,
+    width: u32,
+    height: u32,
+) -> impl Iterator<Item = (u32, u32)> {
+    // Rows are the major axis of steep lines, the coordinates are swapped so
+    // the rest works along columns
+    let steep = row0.abs_diff(row1) > column0.abs_diff(column1);
+    let (a0, a1, b0, b1, major_size, minor_size) = if steep {
+        (row0, row1, column0, column1, height, width)
+    } else {
+        (column0, column1, row0, row1, width, height)
+    };
+    let (a_forward, b_forward) = (a1 >= a0, b1 >= b0);
+    let length = u128::from(a0.abs_diff(a1));
+    let rise = u128::from(b0.abs_diff(b1));
+    let offset = move |step: u128| (2 * step * rise + length) / (2 * length.max(1));
+    let (a0, b0) = (u128::from(a0), u128::from(b0));
+    let (major_size, minor_size) = (u128::from(major_size), u128::from(minor_size));
+
+    // Steps from `first` to `last` stay inside the grid. A line between two
+    // cells never goes below row or column 0, only the far edges clip it.
+    let (mut first, mut last) = (0, length);
+    if a_forward {
+        match major_size.checked_sub(a0 + 1) {
+            Some(room) => last = last.min(room),
+            None => first = length + 1,
+        }
+    } else if a0 >= major_size {
+        first = first.max(a0 + 1 - major_size);
+    }
+    if b_forward {
+        match minor_size.checked_sub(b0 + 1) {
+            // The last step with `offset(step) <= room`
+            Some(room) if rise > 0 => last = last.min(((2 * room + 1) * length - 1) / (2 * rise)),
+            Some(_) => {}
+            None => first = length + 1,
+        }
+    } else if b0 >= minor_size {
+        // The first step with `offset(step) >= needed`
+        let needed = b0 + 1 - minor_size;
+        first = first.max(((2 * needed - 1) * length).div_ceil(2 * rise));
+    }
+
+    (first..=last).map(move |step| {
+        let a = if a_forward { a0 + step } else { a0 - step };
+        let b = if b_forward {
+            b0 + offset(step)
+        } else {
+            b0 - offset(step)
+        };
+        if steep {
+            (a as u32, b as u32)
+        } else {
+            (b as u32, a as u32)
+        }
+    })
+}
@@ -0,0 +1,110 @@
+// The error type shared by the whole crate.
+//
+// Parsing has its own error types with the details of what went wrong, this
+// wraps them together with the errors from editing a universe, so callers
+// that do a bit of everything only need to handle one type.
+
+use std::fmt;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{ParsePatternError, ParseRleError, ParseRuleError};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A rulestring could not be parsed.
+    Rule(ParseRuleError),
+    /// An RLE pattern could not be decoded.
+    Rle(ParseRleError),
+    /// A pattern in another format could not be decoded.
+    Pattern(ParsePatternError),
+    /// There is no pattern by this name in the built-in library.
+    UnknownPattern(String),
+    /// Advancing a `HashLife` plane would take the pattern past the
+    /// coordinates an `i64` can hold, or the generation past a `u64`, or a
+    /// cell to set is past the edge of the plane.
+    PlaneOverflow,
+    /// A `width` x `height` universe would have more cells than a universe
+    /// can hold.
+    TooBig { width: u32, height: u32 },
+    /// Rendering would make an image bigger than a pixel buffer can hold.
+    ImageTooBig,
+    /// A cell outside of a `width` x `height` universe was edited.
+    OutOfBounds {
+        row: u32,
+        column: u32,
+        width: u32,
+        height: u32,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Rule(err) => err.fmt(f),
+            Error::Rle(err) => err.fmt(f),
+            Error::Pattern(err) => err.fmt(f),
+            Error::UnknownPattern(name) => write!(f, "there is no pattern called \"{}\"", name),
+            Error::PlaneOverflow => write!(f, "the pattern would outgrow the plane"),
+            Error::TooBig { width, height } => {
+                write!(
+                    f,
+                    "a {}x{} universe would have too many cells",
+                    width, height
+                )
+            }
+            Error::ImageTooBig => write!(f, "the image would be too big to render"),
+            Error::OutOfBounds {
+                row,
+                column,
+                width,
+                height,
+            } => write!(
+                f,
+                "cell ({}, {}) is outside of the {}x{} universe",
+                row, column, width, height
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Rule(err) => Some(err),
+            Error::Rle(err) => Some(err),
+            Error::Pattern(err) => Some(err),
+            Error::UnknownPattern(_)
+            | Error::PlaneOverflow
+            | Error::TooBig { .. }
+            | Error::ImageTooBig
+            | Error::OutOfBounds { .. } => None,
+        }
+    }
+}
+
+impl From<ParseRuleError> for Error {
+    fn from(err: ParseRuleError) -> Error {
+        Error::Rule(err)
+    }
+}
+
+impl From<ParseRleError> for Error {
+    fn from(err: ParseRleError) -> Error {
+        Error::Rle(err)
+    }
+}
+
+impl From<ParsePatternError> for Error {
+    fn from(err: ParsePatternError) -> Error {
+        Error::Pattern(err)
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl From<Error> for JsValue {
+    fn from(err: Error) -> JsValue {
+        js_sys::Error::new(&err.to_string()).into()
+    }
+}
@@ -0,0 +1,173 @@
+// Reading patterns in any of the supported file formats.
+//
+// Besides RLE (see the `rle` module) patterns come as plaintext `.cells`
+// files (see `plaintext`), as Life 1.05 or 1.06 files (see `life`) and as
+// macrocell files (see `macrocell`), which go through `HashLife`. The
+// format of a pattern is told apart by its first lines, every format decodes
+// into the same `RlePattern`.
+
+use std::fmt;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    life, macrocell, plaintext, rle, BoundingBox, Error, HashLife, ParseRleError, RlePattern, Rule,
+    MAX_CELLS,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Rle,
+    Plaintext,
+    Life105,
+    Life106,
+    Macrocell,
+}
+
+impl Format {
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Rle => "RLE",
+            Format::Plaintext => "plaintext",
+            Format::Life105 => "Life 1.05",
+            Format::Life106 => "Life 1.06",
+            Format::Macrocell => "macrocell",
+        }
+    }
+}
+
+/// Error returned when a pattern in any format but RLE can not be decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParsePatternError {
+    format: &'static str,
+    reason: String,
+}
+
+impl ParsePatternError {
+    pub(crate) fn new(format: Format, reason: impl Into<String>) -> ParsePatternError {
+        ParsePatternError {
+            format: format.name(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParsePatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid {} pattern: {}", self.format, self.reason)
+    }
+}
+
+impl std::error::Error for ParsePatternError {}
+
+#[cfg(feature = "wasm")]
+impl From<ParsePatternError> for JsValue {
+    fn from(err: ParsePatternError) -> JsValue {
+        js_sys::Error::new(&err.to_string()).into()
+    }
+}
+
+/// Guess the format of a pattern from its first lines.
+pub fn detect(text: &str) -> Format {
+    if macrocell::is_macrocell(text) {
+        return Format::Macrocell;
+    }
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    let first = match lines.clone().next() {
+        Some(first) => first,
+        None => return Format::Rle,
+    };
+    if first.starts_with("#Life 1.06") {
+        return Format::Life106;
+    }
+    if first.starts_with("#Life 1.05") {
+        return Format::Life105;
+    }
+
+    // Plaintext has `!` comments and rows of `.` and `O`, while RLE has `#`
+    // comments, an `x = ` header and runs ending in a `!`
+    let is_plaintext_line =
+        |line: &str| line.starts_with('!') || line.chars().all(|c| ".O*".contains(c));
+    if lines.all(is_plaintext_line) {
+        Format::Plaintext
+    } else {
+        Format::Rle
+    }
+}
+
+/// The error for a decoded pattern that can not be used, `ParseRleError` for
+/// RLE and `ParsePatternError` for the other formats.
+pub(crate) fn fit_error(format: Format, reason: String) -> Error {
+    match format {
+        Format::Rle => ParseRleError::new(reason).into(),
+        _ => ParsePatternError::new(format, reason).into(),
+    }
+}
+
+/// Decode a pattern in any of the supported formats.
+pub fn decode(text: &str) -> Result<RlePattern, Error> {
+    Ok(match detect(text) {
+        Format::Rle => rle::decode(text)?,
+        Format::Plaintext => plaintext::decode(text)?,
+        Format::Life105 => life::decode_105(text)?,
+        Format::Life106 => life::decode_106(text)?,
+        Format::Macrocell => {
+            // Checked on the tree, a small file can hold more cells than
+            // could ever be listed
+            let hashlife = HashLife::from_macrocell(text)?;
+            let too_big = hashlife.bounds().is_some_and(|bounds| {
+                bounds
+                    .width()
+                    .checked_mul(bounds.height())
+                    .is_none_or(|area| area > MAX_CELLS)
+            });
+            if too_big {
+                let reason = "the pattern is too big for a Universe, use HashLife";
+                return Err(ParsePatternError::new(Format::Macrocell, reason).into());
+            }
+            let cells = hashlife.live_cells();
+            from_cells(&cells, hashlife.rule().parse().ok(), Format::Macrocell)?
+        }
+    })
+}
+
+/// Build a pattern from live cells anywhere on the plane, moving them so the
+/// top left corner of their bounding box is at the origin. Fails if the
+/// bounding box is more than `u32::MAX` cells wide or high.
+pub(crate) fn from_cells(
+    cells: &[(i64, i64)],
+    rule: Option<Rule>,
+    format: Format,
+) -> Result<RlePattern, ParsePatternError> {
+    let bounds = BoundingBox::around(cells).unwrap_or(BoundingBox {
+        top: 0,
+        left: 0,
+        bottom: 0,
+        right: 0,
+    });
+    let max = u64::from(u32::MAX);
+    if bounds.width() > max || bounds.height() > max {
+        let reason = format!(
+            "the cells span {}x{}, too far apart for a pattern",
+            bounds.width(),
+            bounds.height()
+        );
+        return Err(ParsePatternError::new(format, reason));
+    }
+    Ok(RlePattern {
+        width: bounds.width() as u32,
+        height: bounds.height() as u32,
+        rule,
+        cells: cells
+            .iter()
+            .map(|&(row, column)| {
+                (
+                    (row as u64).wrapping_sub(bounds.top as u64) as u32,
+                    (column as u64).wrapping_sub(bounds.left as u64) as u32,
+                )
+            })
+            .collect(),
+        dying: Vec::new(),
+    })
+}
@@ -0,0 +1,723 @@
+// An implementation of Bill Gosper's HashLife algorithm.
+//
+// The plane is a quadtree. A node of level `n` is a square of 2^n x 2^n cells
+// made of four nodes of level `n - 1`, down to level 0 nodes which are single
+// dead or alive cells. Nodes are canonicalized, so identical squares anywhere in
+// the pattern (and at any time) are the same node, and the future of a node is
+// memoized: the centre half of a level `n` node after 2^(n-2) generations only
+// depends on the node itself. Repetitive patterns can then be advanced
+// exponentially far with very little work.
+//
+// Unlike `Universe` the plane is unbounded, cells are addressed by `i64` rows
+// and columns around an origin in the middle of the root node.
+
+use std::collections::HashMap;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::formats::{Format, ParsePatternError};
+use crate::macrocell::{self, Macrocell, McNode, LEAF_LEVEL};
+use crate::{rle, BoundingBox, Error, ParseRleError, ParseRuleError, Rule};
+
+type NodeId = u32;
+
+// The two level 0 nodes
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+// The root is never smaller than this, 8x8 cells
+const MIN_ROOT_LEVEL: u8 = 3;
+
+// Coordinates have to fit in an i64
+const MAX_LEVEL: u8 = 62;
+
+// Rows and columns in `-PLANE_HALF..PLANE_HALF` are covered by a root of
+// MAX_LEVEL, the cells that can be set
+const PLANE_HALF: i64 = 1 << (MAX_LEVEL - 1);
+
+// The biggest single step, 2^MAX_STEP generations, needs a root of level
+// MAX_STEP + 3 with room to grow into
+const MAX_STEP: u8 = MAX_LEVEL - 4;
+
+// Once the node table grows past this many nodes it is garbage collected
+// before the next step
+const MAX_NODES: usize = 1 << 22;
+
+// Whether a cell can be set without the root outgrowing MAX_LEVEL
+fn on_plane(row: i64, column: i64) -> bool {
+    (-PLANE_HALF..PLANE_HALF).contains(&row) && (-PLANE_HALF..PLANE_HALF).contains(&column)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Quad {
+    nw: NodeId,
+    ne: NodeId,
+    sw: NodeId,
+    se: NodeId,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    quad: Quad,
+    level: u8,
+    population: u64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct HashLife {
+    nodes: Vec<Node>,
+    // Canonical node for every quad built so far
+    index: HashMap<Quad, NodeId>,
+    // Memoized futures, `(node, j)` -> centre of `node` after 2^j generations
+    results: HashMap<(NodeId, u8), NodeId>,
+    // Empty node for every level, built on demand
+    empty: Vec<NodeId>,
+    root: NodeId,
+    generation: u64,
+    rule: Rule,
+}
+
+impl HashLife {
+    fn node(&self, id: NodeId) -> Node {
+        self.nodes[id as usize]
+    }
+
+    fn level(&self, id: NodeId) -> u8 {
+        self.nodes[id as usize].level
+    }
+
+    // The canonical node made of four nodes of the same level
+    fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        let quad = Quad { nw, ne, sw, se };
+        if let Some(&id) = self.index.get(&quad) {
+            return id;
+        }
+
+        // Saturates, a macrocell file can describe more cells than a u64
+        // can count
+        let population = [nw, ne, sw, se].iter().fold(0u64, |sum, &child| {
+            sum.saturating_add(self.node(child).population)
+        });
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            quad,
+            level: self.level(nw) + 1,
+            population,
+        });
+        self.index.insert(quad, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let below = *self.empty.last().unwrap();
+            let node = self.join(below, below, below, below);
+            self.empty.push(node);
+        }
+        self.empty[level as usize]
+    }
+
+    // The centre half of a node, one level down
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let q = self.node(id).quad;
+        let (nw, ne, sw, se) = (
+            self.node(q.nw).quad,
+            self.node(q.ne).quad,
+            self.node(q.sw).quad,
+            self.node(q.se).quad,
+        );
+        self.join(nw.se, ne.sw, sw.ne, se.nw)
+    }
+
+    // Surround the root with empty space, doubling its size around the origin
+    fn expand(&mut self) {
+        let level = self.level(self.root);
+        let q = self.node(self.root).quad;
+        let e = self.empty(level - 1);
+        let nw = self.join(e, e, e, q.nw);
+        let ne = self.join(e, e, q.ne, e);
+        let sw = self.join(e, q.sw, e, e);
+        let se = self.join(q.se, e, e, e);
+        self.root = self.join(nw, ne, sw, se);
+    }
+
+    // Work out the next generation of the centre 2x2 cells of a 4x4 node
+    fn base_successor(&mut self, id: NodeId) -> NodeId {
+        let q = self.node(id).quad;
+        let mut grid = [[false; 4]; 4];
+        for (quadrant, &(top, left)) in [q.nw, q.ne, q.sw, q.se]
+            .iter()
+            .zip([(0, 0), (0, 2), (2, 0), (2, 2)].iter())
+        {
+            let cells = self.node(*quadrant).quad;
+            grid[top][left] = cells.nw == ALIVE;
+            grid[top][left + 1] = cells.ne == ALIVE;
+            grid[top + 1][left] = cells.sw == ALIVE;
+            grid[top + 1][left + 1] = cells.se == ALIVE;
+        }
+
+        let mut next = [DEAD; 4];
+        for (i, &(row, col)) in [(1, 1), (1, 2), (2, 1), (2, 2)].iter().enumerate() {
+            // Count the 3x3 block around the cell, then leave the cell itself out
+            let block = grid[row - 1..=row + 1]
+                .iter()
+                .flat_map(|line| &line[col - 1..=col + 1])
+                .filter(|&&alive| alive)
+                .count() as u8;
+            let live_neighbours = block - grid[row][col] as u8;
+            let alive = if grid[row][col] {
+                self.rule.survives(live_neighbours)
+            } else {
+                self.rule.is_born(live_neighbours)
+            };
+            next[i] = if alive { ALIVE } else { DEAD };
+        }
+        self.join(next[0], next[1], next[2], next[3])
+    }
+
+    // The centre half of a level `n` node after 2^j generations, j <= n - 2
+    fn successor(&mut self, id: NodeId, j: u8) -> NodeId {
+        let node = self.node(id);
+        if node.population == 0 {
+            return self.empty(node.level - 1);
+        }
+        if node.level == 2 {
+            return self.base_successor(id);
+        }
+        if let Some(&result) = self.results.get(&(id, j)) {
+            return result;
+        }
+
+        // Nine overlapping sub-squares of half the size, covering the node
+        let q = node.quad;
+        let (a, b, c, d) = (
+            self.node(q.nw).quad,
+            self.node(q.ne).quad,
+            self.node(q.sw).quad,
+            self.node(q.se).quad,
+        );
+        let subsquares = [
+            q.nw,
+            self.join(a.ne, b.nw, a.se, b.sw),
+            q.ne,
+            self.join(a.sw, a.se, c.nw, c.ne),
+            self.join(a.se, b.sw, c.ne, d.nw),
+            self.join(b.sw, b.se, d.nw, d.ne),
+            q.sw,
+            self.join(c.ne, d.nw, c.se, d.sw),
+            q.se,
+        ];
+
+        // At full speed each half of the way takes 2^(n-3) generations,
+        // otherwise all the time is spent in the first half
+        let full_speed = j == node.level - 2;
+        let first_step = if full_speed { node.level - 3 } else { j };
+        let mut r = [DEAD; 9];
+        for (result, &square) in r.iter_mut().zip(subsquares.iter()) {
+            *result = self.successor(square, first_step);
+        }
+
+        let quarters = [
+            self.join(r[0], r[1], r[3], r[4]),
+            self.join(r[1], r[2], r[4], r[5]),
+            self.join(r[3], r[4], r[6], r[7]),
+            self.join(r[4], r[5], r[7], r[8]),
+        ];
+        let mut next = [DEAD; 4];
+        for (result, &quarter) in next.iter_mut().zip(quarters.iter()) {
+            *result = if full_speed {
+                self.successor(quarter, node.level - 3)
+            } else {
+                self.centre(quarter)
+            };
+        }
+
+        let result = self.join(next[0], next[1], next[2], next[3]);
+        self.results.insert((id, j), result);
+        result
+    }
+
+    // Copy the tree under the root into a fresh node table, dropping every
+    // node and memoized result that is no longer reachable
+    fn collect_garbage(&mut self) {
+        let mut fresh = HashLife::with_rule(self.rule);
+        fresh.generation = self.generation;
+        let mut copied = HashMap::new();
+        copied.insert(DEAD, DEAD);
+        copied.insert(ALIVE, ALIVE);
+        fresh.root = self.copy_into(&mut fresh, self.root, &mut copied);
+        *self = fresh;
+    }
+
+    fn copy_into(
+        &self,
+        fresh: &mut HashLife,
+        id: NodeId,
+        copied: &mut HashMap<NodeId, NodeId>,
+    ) -> NodeId {
+        if let Some(&new_id) = copied.get(&id) {
+            return new_id;
+        }
+        let q = self.node(id).quad;
+        let nw = self.copy_into(fresh, q.nw, copied);
+        let ne = self.copy_into(fresh, q.ne, copied);
+        let sw = self.copy_into(fresh, q.sw, copied);
+        let se = self.copy_into(fresh, q.se, copied);
+        let new_id = fresh.join(nw, ne, sw, se);
+        copied.insert(id, new_id);
+        new_id
+    }
+
+    // Half the side of the root, the root covers rows and columns in
+    // `-half..half`
+    fn half_size(&self) -> i64 {
+        1 << (self.level(self.root) - 1)
+    }
+
+    fn contains(&self, row: i64, column: i64) -> bool {
+        let half = self.half_size();
+        (-half..half).contains(&row) && (-half..half).contains(&column)
+    }
+
+    // Set a cell in the tree under `id`, with `row` and `column` relative to
+    // the top left of the node
+    fn set_in(&mut self, id: NodeId, row: i64, column: i64, alive: bool) -> NodeId {
+        let node = self.node(id);
+        if node.level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+        let half = 1 << (node.level - 1);
+        let Quad {
+            mut nw,
+            mut ne,
+            mut sw,
+            mut se,
+        } = node.quad;
+        match (row < half, column < half) {
+            (true, true) => nw = self.set_in(nw, row, column, alive),
+            (true, false) => ne = self.set_in(ne, row, column - half, alive),
+            (false, true) => sw = self.set_in(sw, row - half, column, alive),
+            (false, false) => se = self.set_in(se, row - half, column - half, alive),
+        }
+        self.join(nw, ne, sw, se)
+    }
+
+    // The cell has to be `on_plane`
+    fn set_cell(&mut self, row: i64, column: i64, alive: bool) {
+        while !self.contains(row, column) {
+            self.expand();
+        }
+        let half = self.half_size();
+        self.root = self.set_in(self.root, row + half, column + half, alive);
+    }
+
+    // Push every live cell under `id` onto `cells`, `top` and `left` being
+    // the coordinates of the top left corner of the node
+    fn collect_cells(&self, id: NodeId, top: i64, left: i64, cells: &mut Vec<(i64, i64)>) {
+        let node = self.node(id);
+        if node.population == 0 {
+            return;
+        }
+        if node.level == 0 {
+            cells.push((top, left));
+            return;
+        }
+        let half = 1 << (node.level - 1);
+        self.collect_cells(node.quad.nw, top, left, cells);
+        self.collect_cells(node.quad.ne, top, left + half, cells);
+        self.collect_cells(node.quad.sw, top + half, left, cells);
+        self.collect_cells(node.quad.se, top + half, left + half, cells);
+    }
+
+    // The box around the live cells under `id` as `(top, left, bottom,
+    // right)`, inclusive and relative to the top left of the node. Memoized,
+    // as repetitive trees share their nodes.
+    fn node_bounds(
+        &self,
+        id: NodeId,
+        memo: &mut HashMap<NodeId, Option<(i64, i64, i64, i64)>>,
+    ) -> Option<(i64, i64, i64, i64)> {
+        let node = self.node(id);
+        if node.population == 0 {
+            return None;
+        }
+        if node.level == 0 {
+            return Some((0, 0, 0, 0));
+        }
+        if let Some(&bounds) = memo.get(&id) {
+            return bounds;
+        }
+        let half = 1 << (node.level - 1);
+        let q = node.quad;
+        let bounds = [
+            (q.nw, 0, 0),
+            (q.ne, 0, half),
+            (q.sw, half, 0),
+            (q.se, half, half),
+        ]
+        .iter()
+        .filter_map(|&(child, top, left)| {
+            let (t, l, b, r) = self.node_bounds(child, memo)?;
+            Some((top + t, left + l, top + b, left + r))
+        })
+        .reduce(|(t0, l0, b0, r0), (t1, l1, b1, r1)| {
+            (t0.min(t1), l0.min(l1), b0.max(b1), r0.max(r1))
+        });
+        memo.insert(id, bounds);
+        bounds
+    }
+
+    // The node for the 2^level x 2^level cells at `top`, `left` of an 8x8
+    // block where bit `row * 8 + column` is set for live cells
+    fn build_leaf(&mut self, bits: u64, level: u8, top: u32, left: u32) -> NodeId {
+        if level == 0 {
+            return if bits & (1 << (top * 8 + left)) != 0 {
+                ALIVE
+            } else {
+                DEAD
+            };
+        }
+        let half = 1 << (level - 1);
+        let nw = self.build_leaf(bits, level - 1, top, left);
+        let ne = self.build_leaf(bits, level - 1, top, left + half);
+        let sw = self.build_leaf(bits, level - 1, top + half, left);
+        let se = self.build_leaf(bits, level - 1, top + half, left + half);
+        self.join(nw, ne, sw, se)
+    }
+
+    // Number the non-empty nodes under `id` for a macrocell file, after the
+    // nodes they are made of. Empty nodes are number 0.
+    fn number_nodes(
+        &self,
+        id: NodeId,
+        numbers: &mut HashMap<NodeId, usize>,
+        nodes: &mut Vec<McNode>,
+    ) -> usize {
+        let node = self.node(id);
+        if node.population == 0 {
+            return 0;
+        }
+        if let Some(&number) = numbers.get(&id) {
+            return number;
+        }
+
+        let mc_node = if node.level == LEAF_LEVEL {
+            let mut cells = Vec::new();
+            self.collect_cells(id, 0, 0, &mut cells);
+            McNode::Leaf(
+                cells
+                    .iter()
+                    .fold(0, |bits, &(row, column)| bits | 1 << (row * 8 + column)),
+            )
+        } else {
+            let q = node.quad;
+            McNode::Node {
+                level: node.level,
+                quadrants: [
+                    self.number_nodes(q.nw, numbers, nodes),
+                    self.number_nodes(q.ne, numbers, nodes),
+                    self.number_nodes(q.sw, numbers, nodes),
+                    self.number_nodes(q.se, numbers, nodes),
+                ],
+            }
+        };
+        nodes.push(mc_node);
+        numbers.insert(id, nodes.len());
+        nodes.len()
+    }
+
+    fn with_rule(rule: Rule) -> HashLife {
+        let leaf = |level| Node {
+            quad: Quad {
+                nw: DEAD,
+                ne: DEAD,
+                sw: DEAD,
+                se: DEAD,
+            },
+            level,
+            population: 0,
+        };
+        let mut hashlife = HashLife {
+            nodes: vec![
+                leaf(0),
+                Node {
+                    population: 1,
+                    ..leaf(0)
+                },
+            ],
+            index: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            generation: 0,
+            rule,
+        };
+        hashlife.root = hashlife.empty(MIN_ROOT_LEVEL);
+        hashlife
+    }
+
+    /// A plane with live `cells` at `generation`, to write the cells of a
+    /// `Universe` as a macrocell file. The rule is not checked.
+    pub(crate) fn with_cells(rule: Rule, generation: u64, cells: &[(i64, i64)]) -> HashLife {
+        let mut hashlife = HashLife::with_rule(rule);
+        // Cells of a universe are always on the plane
+        for &(row, column) in cells {
+            hashlife.set_cell(row, column, true);
+        }
+        hashlife.generation = generation;
+        hashlife
+    }
+
+    /// Set cells to be alive by passing the row and column of each cell.
+    ///
+    /// Rows and columns have to be in `-2^61..2^61`, fails without setting
+    /// any cell otherwise.
+    pub fn set_cells(&mut self, cells: &[(i64, i64)]) -> Result<(), Error> {
+        if !cells.iter().all(|&(row, column)| on_plane(row, column)) {
+            return Err(Error::PlaneOverflow);
+        }
+        for (row, column) in cells.iter().cloned() {
+            self.set_cell(row, column, true);
+        }
+        Ok(())
+    }
+
+    /// The box around the live cells, `None` if there are none. Worked out
+    /// on the tree, without listing the cells.
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        let half = self.half_size();
+        let (top, left, bottom, right) = self.node_bounds(self.root, &mut HashMap::new())?;
+        Some(BoundingBox {
+            top: top - half,
+            left: left - half,
+            bottom: bottom - half + 1,
+            right: right - half + 1,
+        })
+    }
+
+    /// `(row, column)` of every live cell.
+    pub fn live_cells(&self) -> Vec<(i64, i64)> {
+        let mut cells = Vec::new();
+        let half = self.half_size();
+        self.collect_cells(self.root, -half, -half, &mut cells);
+        cells
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl HashLife {
+    /// An empty plane running Conway's rule.
+    pub fn new() -> HashLife {
+        HashLife::with_rule(Rule::default())
+    }
+
+    /// Create a plane from an RLE pattern, with the top left corner of the
+    /// pattern at the origin.
+    pub fn from_rle(rle: &str) -> Result<HashLife, ParseRleError> {
+        let mut hashlife = HashLife::new();
+        hashlife.load_rle(rle)?;
+        Ok(hashlife)
+    }
+
+    /// Replace the whole plane with an RLE pattern, with the top left corner
+    /// of the pattern at the origin. The rule is changed too if the pattern
+    /// has one.
+    pub fn load_rle(&mut self, rle: &str) -> Result<(), ParseRleError> {
+        let pattern = rle::decode(rle)?;
+        let rule = pattern.rule.unwrap_or(self.rule);
+        if let Some(reason) = rule.unbounded_error() {
+            return Err(ParseRleError::new(reason));
+        }
+
+        *self = HashLife::with_rule(rule);
+        for (row, column) in pattern.cells {
+            self.set_cell(i64::from(row), i64::from(column), true);
+        }
+        Ok(())
+    }
+
+    /// Encode the bounding box of all live cells as an RLE pattern.
+    pub fn to_rle(&self) -> String {
+        rle::encode_cells(&self.rule, &self.live_cells())
+    }
+
+    /// Create a plane from a Golly macrocell (`.mc`) file.
+    pub fn from_macrocell(mc: &str) -> Result<HashLife, ParsePatternError> {
+        let mut hashlife = HashLife::new();
+        hashlife.load_macrocell(mc)?;
+        Ok(hashlife)
+    }
+
+    /// Replace the whole plane with a Golly macrocell (`.mc`) file, with the
+    /// root of the file centered on the origin. The rule and generation are
+    /// changed too if the file has them.
+    pub fn load_macrocell(&mut self, mc: &str) -> Result<(), ParsePatternError> {
+        let macrocell = macrocell::decode(mc)?;
+        let rule = macrocell.rule.unwrap_or(self.rule);
+        if let Some(reason) = rule.unbounded_error() {
+            return Err(ParsePatternError::new(Format::Macrocell, reason));
+        }
+
+        *self = HashLife::with_rule(rule);
+        let mut ids = Vec::with_capacity(macrocell.nodes.len());
+        for node in macrocell.nodes.iter() {
+            let id = match *node {
+                McNode::Leaf(bits) => self.build_leaf(bits, LEAF_LEVEL, 0, 0),
+                McNode::Node { level, quadrants } => {
+                    let mut children = [DEAD; 4];
+                    for (child, &number) in children.iter_mut().zip(quadrants.iter()) {
+                        *child = match number {
+                            0 => self.empty(level - 1),
+                            number => ids[number - 1],
+                        };
+                    }
+                    self.join(children[0], children[1], children[2], children[3])
+                }
+            };
+            ids.push(id);
+        }
+        if let Some(&root) = ids.last() {
+            self.root = root;
+        }
+        self.generation = macrocell.generation;
+        Ok(())
+    }
+
+    /// Encode the whole plane as a Golly macrocell (`.mc`) file.
+    pub fn to_macrocell(&self) -> String {
+        let mut nodes = Vec::new();
+        self.number_nodes(self.root, &mut HashMap::new(), &mut nodes);
+        macrocell::encode(&Macrocell {
+            rule: Some(self.rule),
+            generation: self.generation,
+            nodes,
+        })
+    }
+
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    /// Change the birth/survival rule from a rulestring like `B36/S23` or `23/36`.
+    /// Rejects the same rules as `SparseUniverse::set_rule`.
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), ParseRuleError> {
+        let rule: Rule = rule.parse()?;
+        if let Some(reason) = rule.unbounded_error() {
+            return Err(ParseRuleError::new(&rule.to_string(), reason));
+        }
+        self.rule = rule;
+        // Memoized futures were worked out with the old rule
+        self.results.clear();
+        Ok(())
+    }
+
+    /// Number of generations the pattern has been advanced by.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Number of live cells, `u64::MAX` for any more than that.
+    pub fn population(&self) -> u64 {
+        self.node(self.root).population
+    }
+
+    pub fn is_alive(&self, row: i64, column: i64) -> bool {
+        if !self.contains(row, column) {
+            return false;
+        }
+        let half = self.half_size();
+        let (mut id, mut row, mut column) = (self.root, row + half, column + half);
+        while self.level(id) > 0 {
+            let half = 1 << (self.level(id) - 1);
+            let q = self.node(id).quad;
+            id = match (row < half, column < half) {
+                (true, true) => q.nw,
+                (true, false) => q.ne,
+                (false, true) => q.sw,
+                (false, false) => q.se,
+            };
+            row %= half;
+            column %= half;
+        }
+        id == ALIVE
+    }
+
+    /// Flip a cell between dead and alive. Fails like `set_cells` for cells
+    /// outside `-2^61..2^61`.
+    pub fn toggle_cell(&mut self, row: i64, column: i64) -> Result<(), Error> {
+        if !on_plane(row, column) {
+            return Err(Error::PlaneOverflow);
+        }
+        let alive = self.is_alive(row, column);
+        self.set_cell(row, column, !alive);
+        Ok(())
+    }
+
+    /// Advance the pattern by 2^k generations.
+    ///
+    /// `k` can be at most 58. Fails without advancing if `k` is bigger, if
+    /// the pattern could outgrow the coordinates an `i64` can hold or if the
+    /// generation would not fit in a `u64`.
+    pub fn step_pow2(&mut self, k: u8) -> Result<(), Error> {
+        if k > MAX_STEP {
+            return Err(Error::PlaneOverflow);
+        }
+        let generation = self
+            .generation
+            .checked_add(1 << k)
+            .ok_or(Error::PlaneOverflow)?;
+        if self.nodes.len() > MAX_NODES {
+            self.collect_garbage();
+        }
+
+        // The root has to be big enough to be advanced 2^k generations at
+        // once, and every live cell has to be far enough from the edge that
+        // nothing can escape the centre while it is advanced
+        loop {
+            let level = self.level(self.root);
+            let centre = self.centre(self.root);
+            let inner = self.centre(centre);
+            if level >= k + 3 && self.node(inner).population == self.population() {
+                break;
+            }
+            if level >= MAX_LEVEL {
+                return Err(Error::PlaneOverflow);
+            }
+            self.expand();
+        }
+
+        self.root = self.successor(self.root, k);
+        self.generation = generation;
+
+        // Keep the root around the origin from shrinking below the minimum
+        while self.level(self.root) < MIN_ROOT_LEVEL {
+            self.expand();
+        }
+        Ok(())
+    }
+
+    /// Advance the pattern by any number of generations, in steps of at
+    /// most 2^58. Fails like `step_pow2`, after the steps that did fit.
+    pub fn step(&mut self, generations: u64) -> Result<(), Error> {
+        for k in 0..64 {
+            if generations & (1 << k) != 0 {
+                let (k, steps) = if k > MAX_STEP {
+                    (MAX_STEP, 1u64 << (k - MAX_STEP))
+                } else {
+                    (k, 1)
+                };
+                for _ in 0..steps {
+                    self.step_pow2(k)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for HashLife {
+    fn default() -> HashLife {
+        HashLife::new()
+    }
+}
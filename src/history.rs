@@ -0,0 +1,138 @@
+// Undo/redo history for the cells of a universe.
+//
+// Every edit or tick is recorded as the list of cells it changed rather than a
+// copy of the whole universe, so a tick of a mostly still board costs only a
+// few bytes. Steps also remember how many generations they moved the universe
+// by, so undoing a tick goes back a generation. The oldest entries are dropped
+// once the history grows past its memory limit.
+
+use std::collections::VecDeque;
+use std::mem;
+
+use crate::Cell;
+
+// 8 MiB of changes, about a million cells
+const DEFAULT_LIMIT: usize = 8 << 20;
+
+/// One cell changing state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Change {
+    pub index: u32,
+    pub from: Cell,
+    pub to: Cell,
+}
+
+impl Change {
+    // The change that undoes this one
+    fn reversed(self) -> Change {
+        Change {
+            index: self.index,
+            from: self.to,
+            to: self.from,
+        }
+    }
+}
+
+/// One undoable step: the cells it changed and how many generations it
+/// moved the universe by (one for a tick, none for an edit).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub changes: Vec<Change>,
+    pub generations: i64,
+}
+
+impl Step {
+    // The step that undoes this one
+    fn reversed(&self) -> Step {
+        Step {
+            changes: self
+                .changes
+                .iter()
+                .rev()
+                .map(|change| change.reversed())
+                .collect(),
+            generations: -self.generations,
+        }
+    }
+
+    // Memory used by the step, in bytes
+    fn size(&self) -> usize {
+        mem::size_of::<Step>() + mem::size_of_val(&self.changes[..])
+    }
+}
+
+pub struct History {
+    undo: VecDeque<Step>,
+    redo: Vec<Step>,
+    // Memory used by the steps in `undo` and `redo`, in bytes
+    size: usize,
+    limit: usize,
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            size: 0,
+            limit: DEFAULT_LIMIT,
+        }
+    }
+
+    /// Record a new step. Anything that was undone can not be redone anymore.
+    pub fn record(&mut self, step: Step) {
+        if step.changes.is_empty() && step.generations == 0 {
+            return;
+        }
+        for redo in self.redo.drain(..) {
+            self.size -= redo.size();
+        }
+        self.size += step.size();
+        self.undo.push_back(step);
+        self.shrink();
+    }
+
+    /// The step that undoes the last step, which moves over to the redo stack.
+    pub fn undo(&mut self) -> Option<Step> {
+        let step = self.undo.pop_back()?;
+        let undo = step.reversed();
+        self.redo.push(step);
+        Some(undo)
+    }
+
+    /// The last undone step, which moves back to the undo stack.
+    pub fn redo(&mut self) -> Option<Step> {
+        let step = self.redo.pop()?;
+        self.undo.push_back(step.clone());
+        Some(step)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.shrink();
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.size = 0;
+    }
+
+    // Forget the oldest steps until the history fits in its limit
+    fn shrink(&mut self) {
+        while self.size > self.limit {
+            match self.undo.pop_front() {
+                Some(oldest) => self.size -= oldest.size(),
+                None => break,
+            }
+        }
+    }
+}
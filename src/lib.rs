@@ -1,7 +1,58 @@
+mod ages;
+mod anchor;
+mod catalog;
+mod cycle;
+mod draw;
+mod error;
+mod formats;
+mod hashlife;
+mod history;
+mod life;
+mod logging;
+mod macrocell;
+mod packed;
+mod pattern;
+mod plaintext;
+mod random;
+mod renderer;
+mod rle;
+mod rule;
+mod selection;
+mod sparse;
+mod stats;
+mod timeline;
+mod topology;
+mod transform;
 mod utils;
 
+pub use anchor::Anchor;
+pub use catalog::{find_pattern, patterns, PatternInfo, PatternKind};
+pub use cycle::Cycle;
+pub use error::Error;
+pub use formats::ParsePatternError;
+pub use hashlife::HashLife;
+#[cfg(feature = "wasm")]
+pub use logging::ConsoleLogger;
+pub use logging::{set_logger, Logger, NullLogger, StderrLogger};
+pub use packed::PackedUniverse;
+pub use pattern::Pattern;
+pub use renderer::Renderer;
+pub use rle::{ParseRleError, RlePattern};
+pub use rule::{ParseRuleError, Rule};
+pub use selection::{PasteMode, Selection};
+pub use sparse::{BoundingBox, SparseUniverse};
+pub use topology::Topology;
+pub use transform::Transform;
+
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
-extern crate js_sys; // Exposes bindings for all JS global objects
+
+use ages::CellAges;
+use cycle::CycleDetector;
+use history::{Change, History, Step};
+use random::Random;
+use stats::PopulationHistory;
+use timeline::Timeline;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -9,86 +60,138 @@ extern crate js_sys; // Exposes bindings for all JS global objects
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 extern "C" {
     fn alert(s: &str);
 }
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub fn greet(name: &str) {
     alert(name);
 }
 
-extern crate web_sys;
-use web_sys::console;
-
-// A macro to provide `println!(..)`-style syntax for `console.log` logging.
+// A macro to provide `println!(..)`-style syntax for logging, in the browser
+// this goes to `console.log`. See the `logging` module for other backends.
 macro_rules! log {
     ( $( $t:tt )* ) => {
-        console::log_1(&format!( $( $t )* ).into());
+        logging::with_logger(|logger| logger.log(&format!( $( $t )* )));
     }
 }
 
 // Implement a timer struct to check how much time a method took
-// W eare using the `console.time` and `console.timeEnd` from javascript here.
+// In the browser this uses `console.time` and `console.timeEnd` from javascript.
 pub struct Timer<'a> {
     name: &'a str,
 }
 
 impl<'a> Timer<'a> {
     pub fn new(name: &'a str) -> Timer<'a> {
-        console::time_with_label(name);
+        logging::with_logger(|logger| logger.time(name));
         Timer { name }
     }
 }
 
 // We implement the `Drop` trait here so the `drop` method will be called whenever the `timer` instance
-// goes out of scope this will trigger the js function `console.timeEnd` to print the time taken
+// goes out of scope this will stop the timer and report the time taken
 impl<'a> Drop for Timer<'a> {
     fn drop(&mut self) {
-        console::time_end_with_label(self.name);
+        logging::with_logger(|logger| logger.time_end(self.name));
     }
 }
 
 /*
 Type defination for every Cell in the universe
-#[repr(u8)] -> Represent each cell as a single byte
+#[repr(transparent)] -> Represent each cell as a single byte, its state
+0 is dead, 1 is alive and anything above is one of the dying states of a
+Generations rule (see the `rule` module)
 */
-#[wasm_bindgen]
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cell(u8);
+
+// The names keep the spelling from when `Cell` was a two variant enum
+#[allow(non_upper_case_globals)]
+impl Cell {
+    pub const Dead: Cell = Cell(0);
+    pub const Alive: Cell = Cell(1);
+
+    pub fn from_state(state: u8) -> Cell {
+        Cell(state)
+    }
+
+    pub fn state(self) -> u8 {
+        self.0
+    }
+
+    // Dead cells come alive, alive and dying cells die
+    fn toggle(&mut self) {
+        *self = if *self == Cell::Dead {
+            Cell::Alive
+        } else {
+            Cell::Dead
+        };
+    }
+}
+
+/// The states of the bytes in `Universe::cells`, exported to JavaScript as
+/// `Cell` so `Cell.Dead` and `Cell.Alive` keep working there. States 2 and up
+/// are the dying states of a Generations rule.
+#[cfg_attr(feature = "wasm", wasm_bindgen(js_name = Cell))]
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum Cell {
+pub enum CellState {
     Dead = 0,
     Alive = 1,
 }
 
-#[wasm_bindgen]
-impl Cell {
-    fn toggle(&mut self) {
-        *self = match *self {
-            Cell::Dead => Cell::Alive,
-            Cell::Alive => Cell::Dead,
-        };
+impl From<CellState> for Cell {
+    fn from(state: CellState) -> Cell {
+        Cell(state as u8)
     }
 }
 
-#[wasm_bindgen]
+// Universes have at most this many cells, 16384 x 16384. Indices into the
+// cells then always fit a `u32`.
+pub(crate) const MAX_CELLS: u64 = 1 << 28;
+
+// Whether a `width` x `height` universe is small enough
+fn fits_max_cells(width: u32, height: u32) -> bool {
+    u64::from(width) * u64::from(height) <= MAX_CELLS
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct Universe {
     width: u32,
     height: u32,
     cells: Vec<Cell>,
+    rule: Rule,
+    topology: Topology,
+    // Seed of the last random soup, so it can be recreated
+    seed: u64,
+    history: History,
+    generation: u32,
+    timeline: Timeline,
+    // Live cells, kept up to date by every change to the cells
+    population: u32,
+    // Cells born and died in the last tick or edit
+    births: u32,
+    deaths: u32,
+    // Indices of the cells that changed in the last tick or edit
+    changed: Vec<u32>,
+    // Set when the cells were replaced in a way `changed` does not describe
+    redraw: bool,
+    population_history: PopulationHistory,
+    cycles: CycleDetector,
+    selection: Option<Selection>,
+    // Only kept while age tracking is on
+    ages: Option<CellAges>,
 }
 
-fn get_random_cell() -> Cell {
-    if js_sys::Math::random() < 0.5 {
-        Cell::Alive
-    } else {
-        Cell::Dead
-    }
-}
-
-// This `impl` block is annotated with #[wasm_bindgen] so that it can be called by JavaScript.
-#[wasm_bindgen]
+// This `impl` block is annotated with #[wasm_bindgen] so that it can be called by JavaScript
+// (when the `wasm` feature is enabled).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Universe {
     // Get 1D array index for a given 2D array index
     fn get_index(&self, row: u32, column: u32) -> usize {
@@ -99,25 +202,31 @@ impl Universe {
     fn live_neighbour_count(&self, row: u32, column: u32) -> u8 {
         let mut count = 0;
 
-        // [self.height - 1, 0, 1] -> Refers to top, itself and bottom rows
-        // self.height - 1 instead of (row - 1) is done to avoid (0 - 1) case
-        // it works since we have modulo. The module handles wrapping around edges
-        for delta_row in [self.height - 1, 0, 1].iter().cloned() {
-            for delta_col in [self.width - 1, 0, 1].iter().cloned() {
+        // [-1, 0, 1] -> Refers to top, itself and bottom rows (left, itself and right columns)
+        // The topology decides where neighbours off the edge of the grid are,
+        // or that there is no neighbour there at all
+        for delta_row in [-1, 0, 1].iter().cloned() {
+            for delta_col in [-1, 0, 1].iter().cloned() {
                 if delta_row == 0 && delta_col == 0 {
                     continue;
                 }
 
-                let neighbour_row = (row + delta_row) % self.height;
-                let neighbor_col = (column + delta_col) % self.width;
-                let idx = self.get_index(neighbour_row, neighbor_col);
-                count += self.cells[idx] as u8; // If cell at 'idx' is alive this will add 1 to count
+                let neighbour = self.topology.wrap(
+                    i64::from(row) + delta_row,
+                    i64::from(column) + delta_col,
+                    self.width,
+                    self.height,
+                );
+                if let Some((neighbour_row, neighbour_col)) = neighbour {
+                    let idx = self.get_index(neighbour_row, neighbour_col);
+                    count += (self.cells[idx] == Cell::Alive) as u8; // Dying cells do not count
+                }
             }
         }
         count
     }
 
-    /// Public methods, exported to JavaScript.
+    // Public methods, exported to JavaScript.
 
     pub fn width(&self) -> u32 {
         self.width
@@ -127,49 +236,694 @@ impl Universe {
         self.height
     }
 
+    /// The cells as one byte per cell holding its state: 0 for dead, 1 for
+    /// alive and 2 and up for the dying states of a Generations rule.
     pub fn cells(&self) -> *const Cell {
         self.cells.as_ptr()
     }
 
+    /// The indices into `cells()` of the cells that changed in the last
+    /// tick or edit, including undo, redo and stepping back, so only those
+    /// need to be drawn again. See `needs_redraw` for when that is not
+    /// enough.
+    pub fn changed_cells(&self) -> *const u32 {
+        self.changed.as_ptr()
+    }
+
+    pub fn changed_cells_len(&self) -> usize {
+        self.changed.len()
+    }
+
+    /// Whether the whole universe has to be drawn again, because it is new
+    /// or was resized since the last tick or edit.
+    pub fn needs_redraw(&self) -> bool {
+        self.redraw
+    }
+
+    /// Turn tracking the age and activity of every cell on or off. Tracking
+    /// starts with every live cell newborn.
+    pub fn set_track_ages(&mut self, track: bool) {
+        self.ages = if track {
+            Some(CellAges::new(self.cells.len()))
+        } else {
+            None
+        };
+    }
+
+    pub fn tracks_ages(&self) -> bool {
+        self.ages.is_some()
+    }
+
+    /// The age of every cell as a `u16`, parallel to `cells()`: how many
+    /// ticks a live cell has survived, 0 for newborn and dead cells. Null
+    /// unless ages are tracked, see `set_track_ages`.
+    ///
+    /// Edits, undo and stepping back make the cells they change newborn
+    /// rather than giving them back their old age.
+    pub fn ages(&self) -> *const u16 {
+        self.ages
+            .as_ref()
+            .map_or(std::ptr::null(), |ages| ages.ages().as_ptr())
+    }
+
+    /// How many times every cell changed since ages were tracked or the
+    /// universe was last reset or randomized, as a `u32` parallel to
+    /// `cells()`. Null unless ages are tracked.
+    pub fn activity(&self) -> *const u32 {
+        self.ages
+            .as_ref()
+            .map_or(std::ptr::null(), |ages| ages.activity().as_ptr())
+    }
+
+    // Replace the cells with the next state, `generations` after the current
+    // one, and record the cells that changed as one step in the undo history
+    fn replace_cells(&mut self, next: Vec<Cell>, generations: i64) {
+        // Only a tick carries on the generations the cycle detector has seen
+        if generations != 1 {
+            self.cycles.clear();
+        }
+        let changes: Vec<Change> = self
+            .cells
+            .iter()
+            .zip(next.iter())
+            .enumerate()
+            .filter(|(_, (from, to))| from != to)
+            .map(|(index, (&from, &to))| Change {
+                index: index as u32,
+                from,
+                to,
+            })
+            .collect();
+        self.count_changes(&changes);
+        self.history.record(Step {
+            changes,
+            generations,
+        });
+        self.cells = next;
+        self.generation = (i64::from(self.generation) + generations) as u32;
+        self.population_history.update(self.generation, self.population);
+    }
+
+    // Apply a step from the undo history
+    fn apply_step(&mut self, step: &Step) {
+        for change in step.changes.iter() {
+            self.cells[change.index as usize] = change.to;
+        }
+        self.count_changes(&step.changes);
+        self.cycles.clear();
+        self.generation = (i64::from(self.generation) + step.generations) as u32;
+        self.population_history.update(self.generation, self.population);
+    }
+
+    // Keep the population, the births and deaths, the changed cells and the
+    // ages if they are tracked up to date with the cells changed by a tick or
+    // an edit
+    fn count_changes(&mut self, changes: &[Change]) {
+        self.births = 0;
+        self.deaths = 0;
+        self.changed.clear();
+        self.redraw = false;
+        for change in changes {
+            if let Some(ages) = &mut self.ages {
+                ages.changed(change.index as usize);
+            }
+            self.changed.push(change.index);
+            if change.to == Cell::Alive {
+                self.population += 1;
+                self.births += 1;
+            } else if change.from == Cell::Alive {
+                self.population -= 1;
+                self.deaths += 1;
+            }
+        }
+    }
+
+    // Set cells by index, one after the other, and record the cells that
+    // changed as one edit in the undo history
+    fn edit_cells(&mut self, edits: impl IntoIterator<Item = (usize, Cell)>) {
+        let mut changes = Vec::new();
+        for (idx, to) in edits {
+            let from = self.cells[idx];
+            if from != to {
+                changes.push(Change {
+                    index: idx as u32,
+                    from,
+                    to,
+                });
+                self.cells[idx] = to;
+            }
+        }
+        self.count_changes(&changes);
+        self.population_history.update(self.generation, self.population);
+        self.cycles.clear();
+        self.history.record(Step {
+            changes,
+            generations: 0,
+        });
+    }
+
+    // Fail if `(row, column)` is outside of the universe
+    fn check_bounds(&self, row: u32, column: u32) -> Result<(), Error> {
+        if row < self.height && column < self.width {
+            Ok(())
+        } else {
+            Err(Error::OutOfBounds {
+                row,
+                column,
+                width: self.width,
+                height: self.height,
+            })
+        }
+    }
+
+    /// Toggle a cell between dead and alive. The cell must be inside the
+    /// universe, see `try_toggle_cell`.
     pub fn toggle_cell(&mut self, row: u32, column: u32) {
         let idx = self.get_index(row, column);
+        let from = self.cells[idx];
         self.cells[idx].toggle();
+        let changes = vec![Change {
+            index: idx as u32,
+            from,
+            to: self.cells[idx],
+        }];
+        self.count_changes(&changes);
+        self.population_history.update(self.generation, self.population);
+        self.cycles.clear();
+        self.history.record(Step {
+            changes,
+            generations: 0,
+        });
+    }
+
+    /// Toggle a cell between dead and alive, or return an error if it is
+    /// outside of the universe.
+    pub fn try_toggle_cell(&mut self, row: u32, column: u32) -> Result<(), Error> {
+        self.check_bounds(row, column)?;
+        self.toggle_cell(row, column);
+        Ok(())
+    }
+
+    /// Add a pattern from the built-in library, see `patterns()`, turned by
+    /// `transform` and with its top left corner at `(row, column)`. Cells
+    /// already alive stay alive.
+    ///
+    /// Fails without touching the universe if there is no such pattern or
+    /// it does not fit.
+    pub fn insert_pattern(
+        &mut self,
+        name: &str,
+        row: u32,
+        column: u32,
+        transform: Transform,
+    ) -> Result<(), Error> {
+        let pattern = find_pattern(name)
+            .ok_or_else(|| Error::UnknownPattern(name.to_string()))?
+            .pattern();
+        let cells: Vec<(u32, u32)> = pattern
+            .cells
+            .iter()
+            .map(|&(r, c)| {
+                let (r, c) = transform.apply(r, c, pattern.width, pattern.height);
+                (row.saturating_add(r), column.saturating_add(c))
+            })
+            .collect();
+        self.try_set_cells(&cells)
+    }
+
+    /// Add `pattern`, turned by `transform`, with the point of it picked by
+    /// `anchor` at `(row, column)`. Cells that end up past an edge wrap
+    /// around as the topology says, or are left out if that edge does not
+    /// wrap, like `paste`. Cells already alive stay alive.
+    pub fn set_pattern(
+        &mut self,
+        pattern: &Pattern,
+        row: u32,
+        column: u32,
+        anchor: Anchor,
+        transform: Transform,
+    ) {
+        if self.cells.is_empty() {
+            return;
+        }
+        let pattern = pattern.transform(transform);
+        // Anchoring the last row and column rather than the sides of the
+        // box puts `BottomRight` on the bottom right cell
+        let (top, left) = anchor.offset(
+            pattern.width().saturating_sub(1),
+            pattern.height().saturating_sub(1),
+            0,
+            0,
+        );
+        let (top, left) = (top + i64::from(row), left + i64::from(column));
+        let cells: Vec<(u32, u32)> = pattern
+            .cells()
+            .iter()
+            .filter_map(|&(r, c)| {
+                self.topology.wrap(top + r, left + c, self.width, self.height)
+            })
+            .collect();
+        self.set_cells(&cells);
+    }
+
+    /// Select the `width` x `height` rectangle with its top left corner at
+    /// `(row, column)`, or the part of it inside the universe.
+    pub fn select(&mut self, row: u32, column: u32, width: u32, height: u32) {
+        self.selection = Selection::clip(row, column, width, height, self.width, self.height);
+    }
+
+    pub fn select_all(&mut self) {
+        self.select(0, 0, self.width, self.height);
+    }
+
+    pub fn deselect(&mut self) {
+        self.selection = None;
+    }
+
+    /// The selected rectangle, `undefined` if nothing is selected.
+    pub fn selection(&self) -> Option<Selection> {
+        self.selection
+    }
+
+    /// The selected cells as an RLE pattern, `undefined` if nothing is
+    /// selected.
+    pub fn copy(&self) -> Option<String> {
+        let selection = self.selection?;
+        let cells: Vec<Cell> = selection
+            .cells()
+            .map(|(row, col)| self.cells[self.get_index(row, col)])
+            .collect();
+        Some(rle::encode(selection.width, &self.rule, &cells))
+    }
+
+    /// Copy the selected cells, then kill them.
+    pub fn cut(&mut self) -> Option<String> {
+        let pattern = self.copy()?;
+        self.erase_selection();
+        Some(pattern)
+    }
+
+    /// Kill every selected cell.
+    pub fn erase_selection(&mut self) {
+        self.fill_region(self.selection, Cell::Dead);
+    }
+
+    /// Bring every selected cell to life.
+    pub fn fill_selection(&mut self) {
+        self.fill_region(self.selection, Cell::Alive);
+    }
+
+    /// Paste an RLE pattern with its top left corner at `(row, column)`,
+    /// combining it with the cells already there as `mode` says. Cells that
+    /// end up past an edge wrap around as the topology says, or are left out
+    /// if that edge does not wrap, like `set_pattern`. The rule of the
+    /// pattern is ignored.
+    pub fn paste(
+        &mut self,
+        rle: &str,
+        row: u32,
+        column: u32,
+        mode: PasteMode,
+    ) -> Result<(), ParseRleError> {
+        let pattern = rle::decode(rle)?;
+        if self.cells.is_empty() {
+            return Ok(());
+        }
+        let (top, left) = (i64::from(row), i64::from(column));
+        let wrap = |r: i64, c: i64| {
+            self.topology.wrap(top + r, left + c, self.width, self.height)
+        };
+
+        let mut next = self.cells.clone();
+        if mode == PasteMode::Overwrite {
+            // A box as big as the universe already covers every cell, however
+            // the edges wrap
+            let rows = pattern.height.min(self.height);
+            let columns = pattern.width.min(self.width);
+            for (r, c) in (0..rows).flat_map(|r| (0..columns).map(move |c| (r, c))) {
+                if let Some((r, c)) = wrap(i64::from(r), i64::from(c)) {
+                    next[self.get_index(r, c)] = Cell::Dead;
+                }
+            }
+        }
+
+        let live = pattern.cells.iter().map(|&(r, c)| (r, c, Cell::Alive));
+        let dying = pattern
+            .dying
+            .iter()
+            .map(|&(r, c, state)| (r, c, Cell::from_state(state)));
+        for (r, c, cell) in live.chain(dying) {
+            if let Some((r, c)) = wrap(i64::from(r), i64::from(c)) {
+                let idx = self.get_index(r, c);
+                match mode {
+                    PasteMode::Xor => next[idx].toggle(),
+                    PasteMode::Or | PasteMode::Overwrite => next[idx] = cell,
+                }
+            }
+        }
+        self.replace_cells(next, 0);
+        Ok(())
+    }
+
+    /// Draw a line of live cells, or dead ones if `alive` is false, from
+    /// `(row0, column0)` to `(row1, column1)`. The part of the line outside
+    /// the universe is left out.
+    pub fn draw_line(
+        &mut self,
+        row0: u32,
+        column0: u32,
+        row1: u32,
+        column1: u32,
+        alive: bool,
+    ) {
+        let cell = if alive { Cell::Alive } else { Cell::Dead };
+        let edits: Vec<(usize, Cell)> =
+            draw::line(row0, column0, row1, column1, self.width, self.height)
+                .map(|(row, col)| (self.get_index(row, col), cell))
+                .collect();
+        self.edit_cells(edits);
+    }
+
+    /// Bring every cell of the `width` x `height` rectangle with its top left
+    /// corner at `(row, column)` to life. The part of the rectangle outside
+    /// the universe is left out.
+    pub fn fill_rect(&mut self, row: u32, column: u32, width: u32, height: u32) {
+        let region = Selection::clip(row, column, width, height, self.width, self.height);
+        self.fill_region(region, Cell::Alive);
+    }
+
+    /// Kill every cell of a rectangle, like `fill_rect`.
+    pub fn clear_rect(&mut self, row: u32, column: u32, width: u32, height: u32) {
+        let region = Selection::clip(row, column, width, height, self.width, self.height);
+        self.fill_region(region, Cell::Dead);
+    }
+
+    /// Fill a rectangle, like `fill_rect`, with a random soup where each cell
+    /// is alive with probability `density`. Unlike `randomize_with_seed` this
+    /// is an edit, the generation and the cells outside are kept.
+    pub fn randomize_rect(
+        &mut self,
+        row: u32,
+        column: u32,
+        width: u32,
+        height: u32,
+        density: f64,
+        seed: u64,
+    ) {
+        let region = match Selection::clip(row, column, width, height, self.width, self.height) {
+            Some(region) => region,
+            None => return,
+        };
+        let mut random = Random::new(seed);
+        let edits: Vec<(usize, Cell)> = region
+            .cells()
+            .map(|(row, col)| {
+                let cell = if random.next_f64() < density {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                };
+                (self.get_index(row, col), cell)
+            })
+            .collect();
+        self.edit_cells(edits);
     }
 
     /// Set the width of the universe.
     ///
-    /// Resets all cells to the dead state.
+    /// Resets all cells to the dead state and the generation to 0, and
+    /// forgets the undo history and past generations.
     pub fn set_width(&mut self, width: u32) {
         self.width = width;
         self.cells = (0..width * self.height).map(|_i| Cell::Dead).collect();
+        self.restart(0);
     }
 
     /// Set the height of the universe.
     ///
-    /// Resets all cells to the dead state.
+    /// Resets all cells to the dead state and the generation to 0, and
+    /// forgets the undo history and past generations.
     pub fn set_height(&mut self, height: u32) {
         self.height = height;
         self.cells = (0..self.width * height).map(|_i| Cell::Dead).collect();
+        self.restart(0);
+    }
+
+    /// Change the size of the universe, keeping the cells that still fit.
+    ///
+    /// `anchor` decides which part of the old cells stays in place, the rest
+    /// of the grid is cropped or padded with dead cells. The generation is
+    /// kept but the undo history and past generations are forgotten.
+    ///
+    /// Fails without touching the universe if it would have too many cells.
+    pub fn resize(&mut self, width: u32, height: u32, anchor: Anchor) -> Result<(), Error> {
+        if !fits_max_cells(width, height) {
+            return Err(Error::TooBig { width, height });
+        }
+        let (top, left) = anchor.offset(self.width, self.height, width, height);
+        let mut next = vec![Cell::Dead; width as usize * height as usize];
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let (new_row, new_col) = (i64::from(row) + top, i64::from(col) + left);
+                if new_row >= 0
+                    && new_row < i64::from(height)
+                    && new_col >= 0
+                    && new_col < i64::from(width)
+                {
+                    next[(new_row * i64::from(width) + new_col) as usize] =
+                        self.cells[self.get_index(row, col)];
+                }
+            }
+        }
+
+        self.width = width;
+        self.height = height;
+        self.cells = next;
+        self.restart(self.generation);
+        Ok(())
+    }
+
+    /// Undo the last edit or tick. Returns false if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.history.undo() {
+            Some(step) => {
+                self.apply_step(&step);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redo the last undone edit or tick. Returns false if there is nothing
+    /// to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.history.redo() {
+            Some(step) => {
+                self.apply_step(&step);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    /// Limit the memory the undo history may use, in bytes. The oldest steps
+    /// are forgotten first.
+    pub fn set_history_limit(&mut self, bytes: usize) {
+        self.history.set_limit(bytes);
     }
 
-    /// Sets the universe to a random state
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// The generation the universe is on, the number of ticks since it was
+    /// created, reset or randomized.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// The number of live cells.
+    pub fn population(&self) -> u32 {
+        self.population
+    }
+
+    /// The number of cells that were born in the last tick, or brought to
+    /// life by the last edit.
+    pub fn births(&self) -> u32 {
+        self.births
+    }
+
+    /// The number of live cells that died or started dying in the last tick,
+    /// or were killed by the last edit.
+    pub fn deaths(&self) -> u32 {
+        self.deaths
+    }
+
+    /// The population of every kept generation, oldest first, for charting.
+    /// The first value is for generation `population_history_start()`.
+    pub fn population_history(&self) -> Vec<u32> {
+        self.population_history.to_vec()
+    }
+
+    pub fn population_history_start(&self) -> u32 {
+        self.population_history.start()
+    }
+
+    /// How many generations of population to keep.
+    pub fn set_population_history_capacity(&mut self, generations: usize) {
+        self.population_history.set_capacity(generations);
+    }
+
+    /// The cycle the universe has settled into, `undefined` until a
+    /// generation has repeated since the last edit or detection was turned
+    /// on.
+    pub fn cycle(&self) -> Option<Cycle> {
+        self.cycles.cycle()
+    }
+
+    /// Whether to look for a cycle while ticking. Off by default, as every
+    /// generation is hashed while it is on. Starts looking all over.
+    pub fn set_detect_cycles(&mut self, detect: bool) {
+        self.cycles.set_enabled(detect);
+    }
+
+    /// Whether a generation that repeats somewhere else also counts as a
+    /// cycle, to find spaceships. Starts looking for a cycle all over.
+    pub fn set_detect_spaceships(&mut self, detect: bool) {
+        self.cycles.set_translations(detect);
+    }
+
+    /// How many past generations to compare against, longer cycles are not
+    /// found.
+    pub fn set_cycle_detection_limit(&mut self, generations: usize) {
+        self.cycles.set_limit(generations);
+    }
+
+    /// The earliest generation that `seek` can go back to, `undefined` if
+    /// no past generations are kept.
+    pub fn oldest_generation(&self) -> Option<u32> {
+        self.timeline.first_generation()
+    }
+
+    /// Go back to the previous generation, as it was just before it was
+    /// ticked. Returns false if that generation is no longer kept.
+    pub fn step_back(&mut self) -> bool {
+        match self.generation.checked_sub(1) {
+            Some(previous) => self.seek(previous),
+            None => false,
+        }
+    }
+
+    /// Go to any generation. Earlier generations are rebuilt from the kept
+    /// past generations, later ones are ticked to.
+    ///
+    /// Returns false and leaves the universe alone if `generation` is in the
+    /// past and no longer kept.
+    pub fn seek(&mut self, generation: u32) -> bool {
+        if generation >= self.generation {
+            for _ in self.generation..generation {
+                self.tick();
+            }
+            return true;
+        }
+
+        match self.timeline.get(generation) {
+            Some(cells) => {
+                let generations = i64::from(generation) - i64::from(self.generation);
+                self.replace_cells(cells, generations);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// How many past generations to keep for `step_back` and `seek`.
+    pub fn set_timeline_capacity(&mut self, generations: usize) {
+        self.timeline.set_capacity(generations);
+    }
+
+    /// The rule the universe is running, as a `B3/S23` style rulestring.
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    /// Change the birth/survival rule from a rulestring like `B36/S23` or `23/36`,
+    /// or a Generations rulestring like `B2/S/C3`.
+    ///
+    /// Returns an error and keeps the current rule if the rulestring is malformed.
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), ParseRuleError> {
+        self.rule = rule.parse()?;
+        self.cycles.clear();
+        Ok(())
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Change how the edges of the universe are joined together.
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+        self.cycles.clear();
+    }
+
+    /// Sets the universe to a random state, back at generation 0
     pub fn randomize(&mut self) {
+        self.randomize_with_seed(random::random_seed(), 0.5);
+    }
+
+    /// Sets the universe to a random soup where each cell is alive with
+    /// probability `density`. The same seed and density always give the same
+    /// soup.
+    pub fn randomize_with_seed(&mut self, seed: u64, density: f64) {
+        let mut random = Random::new(seed);
+        let mut next = self.cells.clone();
         for row in 0..self.height {
             for col in 0..self.width {
                 let idx = self.get_index(row, col);
-                self.cells[idx] = get_random_cell();
+                next[idx] = if random.next_f64() < density {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                };
             }
         }
+        self.replace_cells(next, -i64::from(self.generation));
+        self.timeline.clear();
+        if let Some(ages) = &mut self.ages {
+            ages.clear();
+        }
+        self.seed = seed;
     }
 
-    /// Resets the universe to all dead cells
+    /// The seed of the last random soup, pass it to `randomize_with_seed`
+    /// to get the same soup again.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Resets the universe to all dead cells, back at generation 0
     pub fn reset(&mut self) {
+        let mut next = self.cells.clone();
         for row in 0..self.height {
             for col in 0..self.width {
                 let idx = self.get_index(row, col);
-                self.cells[idx] = Cell::Dead;
+                next[idx] = Cell::Dead;
             }
         }
+        self.replace_cells(next, -i64::from(self.generation));
+        self.timeline.clear();
+        if let Some(ages) = &mut self.ages {
+            ages.clear();
+        }
     }
 
     // Compute the next generation of the universe
@@ -178,6 +932,18 @@ impl Universe {
         // To track execution time for every tick
         let _timer = Timer::new("Universe::tick");
 
+        // Remember this generation so it can be stepped back to
+        self.timeline.record(self.generation, &self.cells);
+        if self.cycles.is_empty() {
+            self.cycles.record(self.generation, self.width, &self.cells);
+        }
+
+        // Survivors get a tick older, the cells that change are reset when
+        // they are counted
+        if let Some(ages) = &mut self.ages {
+            ages.grow(self.cells.iter().map(|&cell| cell == Cell::Alive));
+        }
+
         let mut next = self.cells.clone(); // Next generation
 
         for row in 0..self.height {
@@ -194,32 +960,19 @@ impl Universe {
                 //     live_neighbours
                 // );
 
-                let next_cell = match (cell, live_neighbours) {
-                    // Rule 1: Any live cell with fewer than two live neighbours
-                    // dies, as if caused by underpopulation.
-                    (Cell::Alive, x) if x < 2 => Cell::Dead,
-
-                    // Rule 2: Any live cell with two or three live neighbours
-                    // lives on to the next generation.
-                    (Cell::Alive, 2) | (Cell::Alive, 3) => Cell::Alive,
-
-                    // Rule 3: Any live cell with more than three live
-                    // neighbours dies, as if by overpopulation.
-                    (Cell::Alive, x) if x > 3 => Cell::Dead,
-
-                    // Rule 4: Any dead cell with exactly three live neighbours
-                    // becomes a live cell, as if by reproduction.
-                    (Cell::Dead, 3) => Cell::Alive,
-
-                    // All other cells remain in the same state.
-                    (otherwise, _) => otherwise,
-                };
+                // A live cell lives on if the rule lets it survive with this
+                // many neighbours, a dead cell becomes alive if the rule has
+                // a birth for this many neighbours. Otherwise live cells die,
+                // or start dying under a Generations rule.
+                let next_cell = self.rule.next(cell, live_neighbours);
 
                 // log!("    it becomes {:?}", next_cell);
                 next[idx] = next_cell;
             }
         }
-        self.cells = next; // Update generation
+        // Counts the births and deaths too
+        self.replace_cells(next, 1); // Update generation
+        self.cycles.record(self.generation, self.width, &self.cells);
     }
 
     // Constructor to initializes the universe with an interesting pattern of live and dead cells
@@ -227,18 +980,85 @@ impl Universe {
         // Init hook to log rust panic to browser console
         utils::set_panic_hook();
 
-        let width = 64;
-        let height = 64;
-
-        let cells = (0..width * height).map(|_| get_random_cell()).collect();
+        let mut universe = Universe::empty(64, 64);
+        universe.randomize();
+        universe.history.clear();
+        universe.redraw = true;
 
         log!("Init Universe from wasm");
 
-        Universe {
-            width,
-            height,
-            cells,
-        }
+        universe
+    }
+
+    /// Create a universe from an RLE pattern, sized to fit the pattern and
+    /// running the rule given in its header (Conway's rule if there is none).
+    pub fn from_rle(rle: &str) -> Result<Universe, ParseRleError> {
+        utils::set_panic_hook();
+
+        Universe::from_decoded(rle::decode(rle)?).map_err(ParseRleError::new)
+    }
+
+    /// Create a universe from a pattern in any supported format (RLE,
+    /// plaintext, Life 1.05, Life 1.06 or macrocell), telling the format from
+    /// its first lines.
+    pub fn from_pattern(pattern: &str) -> Result<Universe, Error> {
+        utils::set_panic_hook();
+
+        let format = formats::detect(pattern);
+        Universe::from_decoded(formats::decode(pattern)?)
+            .map_err(|reason| formats::fit_error(format, reason))
+    }
+
+    /// Replace the contents of the universe with an RLE pattern, centered in
+    /// the universe. The rule is changed too if the pattern has one.
+    ///
+    /// Fails without touching the universe if the pattern does not fit.
+    pub fn load_rle(&mut self, rle: &str) -> Result<(), ParseRleError> {
+        let pattern = rle::decode(rle)?;
+        self.load_decoded(pattern).map_err(ParseRleError::new)
+    }
+
+    /// Replace the contents of the universe with a pattern in any supported
+    /// format, like `load_rle`.
+    pub fn load_pattern(&mut self, pattern: &str) -> Result<(), Error> {
+        let format = formats::detect(pattern);
+        let pattern = formats::decode(pattern)?;
+        self.load_decoded(pattern)
+            .map_err(|reason| formats::fit_error(format, reason))
+    }
+
+    /// Encode the whole universe as an RLE pattern.
+    pub fn to_rle(&self) -> String {
+        rle::encode(self.width, &self.rule, &self.cells)
+    }
+
+    /// Encode the whole universe as a plaintext `.cells` pattern.
+    pub fn to_plaintext(&self) -> String {
+        plaintext::encode(self.width, &self.cells)
+    }
+
+    /// Encode the whole universe as a Life 1.05 pattern.
+    pub fn to_life105(&self) -> String {
+        life::encode_105(self.width, &self.rule, &self.cells)
+    }
+
+    /// Encode the live cells as a Life 1.06 pattern.
+    pub fn to_life106(&self) -> String {
+        life::encode_106(self.width, &self.cells)
+    }
+
+    /// Encode the live cells as a Golly macrocell (`.mc`) file, with the top
+    /// left corner of the universe at the origin.
+    pub fn to_macrocell(&self) -> String {
+        let width = self.width as usize;
+        let cells: Vec<(i64, i64)> = self
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|&(_, &cell)| cell == Cell::Alive)
+            .map(|(idx, _)| ((idx / width) as i64, (idx % width) as i64))
+            .collect();
+        HashLife::with_cells(self.rule, u64::from(self.generation), &cells).to_macrocell()
     }
 
     // Will use our implementation of the display trait to render a string
@@ -248,22 +1068,42 @@ impl Universe {
     }
 }
 
+impl Default for Universe {
+    fn default() -> Universe {
+        Universe::new()
+    }
+}
+
 // Here, we implement the Display trait from Rust's standard library
 // This allows us the diplay our universe to the user
 
 use std::fmt;
 
+// Write the cells row by row, one symbol per cell and a line break after every
+// row. `Display` and the plaintext formats all draw the universe this way.
+pub(crate) fn write_rows<W: fmt::Write>(
+    out: &mut W,
+    width: u32,
+    cells: &[Cell],
+    symbol: impl Fn(Cell) -> char,
+) -> fmt::Result {
+    // Chunk out every row of the universe
+    for line in cells.chunks(width.max(1) as usize) {
+        for &cell in line {
+            write!(out, "{}", symbol(cell))?; // '?' unwraps Result<V> and return V or return Err in case of error
+        }
+        writeln!(out)?; // Line break for rows
+    }
+    Ok(())
+}
+
 impl fmt::Display for Universe {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Chunk out every row of the universe
-        for line in self.cells.as_slice().chunks(self.width as usize) {
-            for &cell in line {
-                let symbol = if cell == Cell::Dead { '◻' } else { '◼' };
-                write!(f, "{}", symbol)?; // '?' unwraps Result<V> and return V or return Err in case of error
-            }
-            write!(f, "\n")?; // Line break for rows
-        }
-        Ok(())
+        write_rows(f, self.width, &self.cells, |cell| match cell {
+            Cell::Dead => '◻',
+            Cell::Alive => '◼',
+            _ => '▣', // Dying
+        })
     }
 }
 
@@ -271,17 +1111,152 @@ impl fmt::Display for Universe {
 // Rust-generated WebAssembly functions cannot return borrowed references.
 // So created a new `impl Universe` without the #[wasm_bindgen] attribute
 impl Universe {
+    // A universe sized to fit a decoded pattern. Returns why not if the
+    // pattern is too big.
+    fn from_decoded(pattern: RlePattern) -> Result<Universe, String> {
+        let (width, height) = (pattern.width.max(1), pattern.height.max(1));
+        if !fits_max_cells(width, height) {
+            return Err(format!(
+                "a {}x{} universe would have more than {} cells",
+                width, height, MAX_CELLS
+            ));
+        }
+        let mut universe = Universe::empty(width, height);
+        universe.rule = pattern.rule.unwrap_or_default();
+        universe.set_cells(&pattern.cells);
+        for &(row, col, state) in pattern.dying.iter() {
+            let idx = universe.get_index(row, col);
+            universe.cells[idx] = Cell::from_state(state);
+        }
+        universe.history.clear();
+        universe.redraw = true;
+        Ok(universe)
+    }
+
+    // Replace the cells with a decoded pattern, centered. Returns why not if
+    // the pattern does not fit.
+    fn load_decoded(&mut self, pattern: RlePattern) -> Result<(), String> {
+        if pattern.width > self.width || pattern.height > self.height {
+            return Err(format!(
+                "a {}x{} pattern does not fit in a {}x{} universe",
+                pattern.width, pattern.height, self.width, self.height
+            ));
+        }
+
+        let top = (self.height - pattern.height) / 2;
+        let left = (self.width - pattern.width) / 2;
+        let mut next = vec![Cell::Dead; self.cells.len()];
+        for (row, col) in pattern.cells {
+            next[self.get_index(top + row, left + col)] = Cell::Alive;
+        }
+        for (row, col, state) in pattern.dying {
+            next[self.get_index(top + row, left + col)] = Cell::from_state(state);
+        }
+        self.replace_cells(next, 0);
+        if let Some(rule) = pattern.rule {
+            self.rule = rule;
+        }
+        Ok(())
+    }
+
+    // A universe of the given size with every cell dead
+    fn empty(width: u32, height: u32) -> Universe {
+        let mut population_history = PopulationHistory::new();
+        population_history.update(0, 0);
+        Universe {
+            width,
+            height,
+            cells: vec![Cell::Dead; width as usize * height as usize],
+            rule: Rule::default(),
+            topology: Topology::default(),
+            seed: 0,
+            history: History::new(),
+            generation: 0,
+            timeline: Timeline::new(),
+            population: 0,
+            births: 0,
+            deaths: 0,
+            changed: Vec::new(),
+            redraw: true,
+            population_history,
+            cycles: CycleDetector::new(),
+            selection: None,
+            ages: None,
+        }
+    }
+
+    // Set every cell of `region` to `cell`
+    fn fill_region(&mut self, region: Option<Selection>, cell: Cell) {
+        if let Some(region) = region {
+            let edits: Vec<(usize, Cell)> = region
+                .cells()
+                .map(|(row, col)| (self.get_index(row, col), cell))
+                .collect();
+            self.edit_cells(edits);
+        }
+    }
+
+    // Start over at `generation` after the cells were replaced wholesale,
+    // forgetting everything about the old cells
+    fn restart(&mut self, generation: u32) {
+        self.selection = None;
+        if self.ages.is_some() {
+            self.ages = Some(CellAges::new(self.cells.len()));
+        }
+        self.history.clear();
+        self.timeline.clear();
+        self.cycles.clear();
+        self.generation = generation;
+        self.population = self
+            .cells
+            .iter()
+            .filter(|&&cell| cell == Cell::Alive)
+            .count() as u32;
+        self.births = 0;
+        self.deaths = 0;
+        self.changed.clear();
+        self.redraw = true;
+        self.population_history.update(generation, self.population);
+    }
+
     /// Get the dead and alive values of the entire universe.
     pub fn get_cells(&self) -> &[Cell] {
         &self.cells
     }
 
+    /// The indices of the cells that changed in the last tick or edit.
+    pub fn get_changed_cells(&self) -> &[u32] {
+        &self.changed
+    }
+
+    /// The age of every cell, if ages are tracked.
+    pub fn get_ages(&self) -> Option<&[u16]> {
+        self.ages.as_ref().map(CellAges::ages)
+    }
+
+    /// How many times every cell changed, if ages are tracked.
+    pub fn get_activity(&self) -> Option<&[u32]> {
+        self.ages.as_ref().map(CellAges::activity)
+    }
+
+    /// Set cells to be alive like `set_cells`, or return an error without
+    /// changing any cell if one of them is outside of the universe.
+    pub fn try_set_cells(&mut self, cells: &[(u32, u32)]) -> Result<(), Error> {
+        for &(row, col) in cells {
+            self.check_bounds(row, col)?;
+        }
+        self.set_cells(cells);
+        Ok(())
+    }
+
     /// Set cells to be alive in a universe by passing the row and column
-    /// of each cell as an array.
+    /// of each cell as an array. Every cell must be inside the universe, see
+    /// `try_set_cells`.
     pub fn set_cells(&mut self, cells: &[(u32, u32)]) {
-        for (row, col) in cells.iter().cloned() {
-            let idx = self.get_index(row, col);
-            self.cells[idx] = Cell::Alive;
-        }
+        let edits: Vec<(usize, Cell)> = cells
+            .iter()
+            .map(|&(row, col)| (self.get_index(row, col), Cell::Alive))
+            .collect();
+        self.edit_cells(edits);
     }
 }
@@ -0,0 +1,129 @@
+// Reading and writing patterns in the Life 1.05 and Life 1.06 formats.
+//
+// Both start with a `#Life 1.05` or `#Life 1.06` line. Life 1.06 is a list of
+// `x y` coordinates of live cells, one per line. Life 1.05 has blocks of rows
+// of `.` (dead) and `*` (alive) cells, each block starting with a `#P x y`
+// line giving the position of its top left corner. `#N` says the pattern runs
+// Conway's rule and `#R 23/3` gives another rule, `#D` lines are comments.
+// Coordinates can be negative, decoded patterns are moved so their top left
+// corner is at the origin. See https://conwaylife.com/wiki/Life_1.05 and
+// https://conwaylife.com/wiki/Life_1.06
+
+use crate::formats::{self, Format, ParsePatternError};
+use crate::{write_rows, Cell, ParseRuleError, RlePattern, Rule};
+
+/// Decode a Life 1.05 pattern into its size, rule and live cells.
+pub fn decode_105(text: &str) -> Result<RlePattern, ParsePatternError> {
+    let error = |reason: String| ParsePatternError::new(Format::Life105, reason);
+    let mut cells = Vec::new();
+    let mut rule = None;
+    // Position of the block and the number of its rows read so far
+    let (mut top, mut left, mut rows) = (0i64, 0i64, 0i64);
+
+    for line in text.lines().map(str::trim) {
+        if line.starts_with("#N") {
+            rule = Some(Rule::CONWAY);
+        } else if let Some(rulestring) = line.strip_prefix("#R") {
+            rule = Some(
+                rulestring
+                    .parse()
+                    .map_err(|err: ParseRuleError| error(err.to_string()))?,
+            );
+        } else if let Some(position) = line.strip_prefix("#P") {
+            let mut numbers = position.split_whitespace().map(str::parse::<i64>);
+            match (numbers.next(), numbers.next(), numbers.next()) {
+                (Some(Ok(x)), Some(Ok(y)), None) => {
+                    left = x;
+                    top = y;
+                    rows = 0;
+                }
+                _ => return Err(error(format!("bad block position \"{}\"", line))),
+            }
+        } else if line.starts_with('#') || line.is_empty() {
+            continue;
+        } else {
+            let past_edge = || error("block runs past the edge of the plane".to_string());
+            let row = top.checked_add(rows).ok_or_else(past_edge)?;
+            for (column, c) in line.chars().enumerate() {
+                match c {
+                    '.' => {}
+                    '*' => {
+                        let column = left.checked_add(column as i64).ok_or_else(past_edge)?;
+                        cells.push((row, column));
+                    }
+                    c => return Err(error(format!("unexpected character '{}'", c))),
+                }
+            }
+            rows += 1;
+        }
+    }
+
+    formats::from_cells(&cells, rule, Format::Life105)
+}
+
+/// Decode a Life 1.06 pattern into its size and live cells.
+pub fn decode_106(text: &str) -> Result<RlePattern, ParsePatternError> {
+    let mut cells = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        let mut numbers = line.split_whitespace().map(str::parse::<i64>);
+        match (numbers.next(), numbers.next(), numbers.next()) {
+            (Some(Ok(x)), Some(Ok(y)), None) => cells.push((y, x)),
+            _ => {
+                return Err(ParsePatternError::new(
+                    Format::Life106,
+                    format!("expected \"x y\" coordinates, got \"{}\"", line),
+                ))
+            }
+        }
+    }
+    formats::from_cells(&cells, None, Format::Life106)
+}
+
+/// Encode a `width` wide grid of cells as a Life 1.05 pattern, in a single
+/// block at the origin.
+pub fn encode_105(width: u32, rule: &Rule, cells: &[Cell]) -> String {
+    let mut output = String::from("#Life 1.05\n");
+    if *rule == Rule::CONWAY {
+        output.push_str("#N\n");
+    } else {
+        // Life 1.05 writes rules in the survival/birth notation
+        let counts = |included: &dyn Fn(u8) -> bool| -> String {
+            (0..=8)
+                .filter(|&n| included(n))
+                .map(|n| n.to_string())
+                .collect()
+        };
+        output.push_str(&format!(
+            "#R {}/{}\n",
+            counts(&|n| rule.survives(n)),
+            counts(&|n| rule.is_born(n))
+        ));
+    }
+    output.push_str("#P 0 0\n");
+    // Writing to a `String` can not fail
+    let _ = write_rows(&mut output, width, cells, |cell| {
+        if cell == Cell::Alive {
+            '*'
+        } else {
+            '.'
+        }
+    });
+    output
+}
+
+/// Encode the live cells of a `width` wide grid as a Life 1.06 pattern.
+pub fn encode_106(width: u32, cells: &[Cell]) -> String {
+    let width = width.max(1) as usize;
+    let mut output = String::from("#Life 1.06\n");
+    for (index, _) in cells
+        .iter()
+        .enumerate()
+        .filter(|&(_, &cell)| cell == Cell::Alive)
+    {
+        output.push_str(&format!("{} {}\n", index % width, index / width));
+    }
+    output
+}
@@ -0,0 +1,97 @@
+// Where log messages and timings go.
+//
+// In the browser they go to the JS console through `console.log`,
+// `console.time` and `console.timeEnd`. Native builds have no console, so they
+// are dropped unless a different `Logger` is installed with `set_logger`, for
+// example `StderrLogger`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+
+/// A backend for the crate's log messages and timers.
+pub trait Logger: Send + Sync {
+    fn log(&self, message: &str);
+
+    /// Start a timer with the given label.
+    fn time(&self, label: &str);
+
+    /// Stop the timer with the given label and report how long it ran.
+    fn time_end(&self, label: &str);
+}
+
+/// Logs to the browser console, only works when running as wasm in a browser.
+#[cfg(feature = "wasm")]
+pub struct ConsoleLogger;
+
+#[cfg(feature = "wasm")]
+impl Logger for ConsoleLogger {
+    fn log(&self, message: &str) {
+        web_sys::console::log_1(&message.into());
+    }
+
+    fn time(&self, label: &str) {
+        web_sys::console::time_with_label(label);
+    }
+
+    fn time_end(&self, label: &str) {
+        web_sys::console::time_end_with_label(label);
+    }
+}
+
+/// Discards everything, the default outside of the browser.
+pub struct NullLogger;
+
+impl Logger for NullLogger {
+    fn log(&self, _message: &str) {}
+
+    fn time(&self, _label: &str) {}
+
+    fn time_end(&self, _label: &str) {}
+}
+
+/// Logs to stderr and measures timers with `std::time::Instant`, for native
+/// builds.
+#[derive(Default)]
+pub struct StderrLogger {
+    timers: Mutex<HashMap<String, Instant>>,
+}
+
+impl Logger for StderrLogger {
+    fn log(&self, message: &str) {
+        eprintln!("{}", message);
+    }
+
+    fn time(&self, label: &str) {
+        let mut timers = self.timers.lock().unwrap();
+        timers.insert(label.to_string(), Instant::now());
+    }
+
+    fn time_end(&self, label: &str) {
+        let started = self.timers.lock().unwrap().remove(label);
+        if let Some(started) = started {
+            eprintln!("{}: {:?}", label, started.elapsed());
+        }
+    }
+}
+
+static LOGGER: RwLock<Option<Arc<dyn Logger>>> = RwLock::new(None);
+
+/// Send the crate's log messages and timings to `logger` from now on.
+pub fn set_logger(logger: Box<dyn Logger>) {
+    *LOGGER.write().unwrap() = Some(Arc::from(logger));
+}
+
+/// Run `f` with the logger that was set, or the default for this target.
+pub(crate) fn with_logger<F: FnOnce(&dyn Logger)>(f: F) {
+    // The lock is released before `f` runs, so a logger may call
+    // `set_logger` itself
+    let logger = LOGGER.read().unwrap().clone();
+    match logger {
+        Some(logger) => f(logger.as_ref()),
+        #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+        None => f(&ConsoleLogger),
+        #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+        None => f(&NullLogger),
+    }
+}
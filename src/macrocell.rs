@@ -0,0 +1,175 @@
+// Reading and writing patterns in Golly's macrocell (`.mc`) format.
+//
+// A macrocell file is a HashLife quadtree written out node by node, so huge
+// but repetitive patterns stay small. After a `[M2]` line and `#` lines (`#R`
+// gives the rule and `#G` the generation) every line is a node, numbered from
+// 1 in order. A level 3 node, 8x8 cells, is written as its rows of `.` (dead)
+// and `*` (alive) cells each ending in `$`, with dead cells at the end of a
+// row and empty rows at the end left out. Bigger nodes are written as
+// `level nw ne sw se`, the numbers of their four quadrants, 0 being an empty
+// quadrant. The last node is the root, centered on the origin. See
+// https://conwaylife.com/wiki/Macrocell
+
+use crate::formats::{Format, ParsePatternError};
+use crate::{ParseRuleError, Rule};
+
+// The level of the nodes written out cell by cell
+pub const LEAF_LEVEL: u8 = 3;
+
+pub enum McNode {
+    /// An 8x8 node, bit `row * 8 + column` is set for live cells.
+    Leaf(u64),
+    /// A bigger node, by the numbers of its quadrants in the order nw, ne,
+    /// sw, se.
+    Node { level: u8, quadrants: [usize; 4] },
+}
+
+pub struct Macrocell {
+    pub rule: Option<Rule>,
+    pub generation: u64,
+    /// Every node after the ones it is made of, the last one is the root.
+    pub nodes: Vec<McNode>,
+}
+
+impl Macrocell {
+    // The level of node number `number`, 0 for the empty node
+    fn level(&self, number: usize) -> Option<u8> {
+        match self.nodes.get(number.checked_sub(1)?)? {
+            McNode::Leaf(_) => Some(LEAF_LEVEL),
+            McNode::Node { level, .. } => Some(*level),
+        }
+    }
+}
+
+/// Whether `text` looks like a macrocell file.
+pub fn is_macrocell(text: &str) -> bool {
+    text.trim_start().starts_with("[M2]")
+}
+
+// Parse the rows of a leaf, `$..*$.*$`
+fn parse_leaf(line: &str) -> Option<u64> {
+    let mut bits = 0;
+    let (mut row, mut column) = (0, 0);
+    for c in line.chars() {
+        match c {
+            '.' => column += 1,
+            '*' if row < 8 && column < 8 => {
+                bits |= 1 << (row * 8 + column);
+                column += 1;
+            }
+            '$' => {
+                row += 1;
+                column = 0;
+            }
+            _ => return None,
+        }
+    }
+    Some(bits)
+}
+
+/// Decode a macrocell file into its rule, generation and nodes.
+pub fn decode(text: &str) -> Result<Macrocell, ParsePatternError> {
+    let error = |reason: String| ParsePatternError::new(Format::Macrocell, reason);
+    if !is_macrocell(text) {
+        return Err(error("expected a [M2] line first".to_string()));
+    }
+
+    let mut macrocell = Macrocell {
+        rule: None,
+        generation: 0,
+        nodes: Vec::new(),
+    };
+    for line in text.trim_start().lines().skip(1).map(str::trim) {
+        if let Some(rule) = line.strip_prefix("#R") {
+            let rule = rule
+                .parse()
+                .map_err(|err: ParseRuleError| error(err.to_string()))?;
+            macrocell.rule = Some(rule);
+        } else if let Some(generation) = line.strip_prefix("#G") {
+            macrocell.generation = generation
+                .trim()
+                .parse()
+                .map_err(|_| error(format!("bad generation \"{}\"", generation.trim())))?;
+        } else if line.starts_with('#') || line.is_empty() {
+            continue;
+        } else if line.starts_with(['.', '*', '$']) {
+            let bits =
+                parse_leaf(line).ok_or_else(|| error(format!("bad 8x8 node \"{}\"", line)))?;
+            macrocell.nodes.push(McNode::Leaf(bits));
+        } else {
+            let numbers: Vec<usize> = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| error(format!("bad node \"{}\"", line)))?;
+            let (level, quadrants) = match numbers[..] {
+                [level, nw, ne, sw, se] => (level, [nw, ne, sw, se]),
+                _ => return Err(error(format!("bad node \"{}\"", line))),
+            };
+            // Quadrants have to be earlier nodes one level down
+            let fits = |number: usize| {
+                number == 0
+                    || macrocell.level(number).map(|below| usize::from(below) + 1) == Some(level)
+            };
+            if level <= usize::from(LEAF_LEVEL) || level > 62 || !quadrants.iter().all(|&n| fits(n))
+            {
+                return Err(error(format!("bad node \"{}\"", line)));
+            }
+            macrocell.nodes.push(McNode::Node {
+                level: level as u8,
+                quadrants,
+            });
+        }
+    }
+    Ok(macrocell)
+}
+
+/// Encode the nodes of a pattern as a macrocell file.
+pub fn encode(macrocell: &Macrocell) -> String {
+    let mut output = String::from("[M2] (wasm-game-of-life)\n");
+    if let Some(rule) = macrocell.rule {
+        output.push_str(&format!("#R {}\n", rule));
+    }
+    if macrocell.generation > 0 {
+        output.push_str(&format!("#G {}\n", macrocell.generation));
+    }
+
+    for node in macrocell.nodes.iter() {
+        match node {
+            McNode::Leaf(bits) => {
+                let rows: Vec<String> = (0..8)
+                    .map(|row| {
+                        let row_bits = (bits >> (row * 8)) & 0xff;
+                        // Dead cells after the last live one are left out
+                        let length = 8 - (row_bits as u8).leading_zeros() as usize;
+                        (0..length)
+                            .map(|column| {
+                                if row_bits & (1 << column) != 0 {
+                                    '*'
+                                } else {
+                                    '.'
+                                }
+                            })
+                            .collect()
+                    })
+                    .collect();
+                let length = rows
+                    .iter()
+                    .rposition(|row| !row.is_empty())
+                    .map_or(0, |last| last + 1);
+                for row in &rows[..length] {
+                    output.push_str(row);
+                    output.push('$');
+                }
+            }
+            McNode::Node { level, quadrants } => {
+                output.push_str(&format!(
+                    "{} {} {} {} {}",
+                    level, quadrants[0], quadrants[1], quadrants[2], quadrants[3]
+                ));
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
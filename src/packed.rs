@@ -0,0 +1,341 @@
+// A universe that stores one bit per cell instead of one byte.
+//
+// Every row is stored as `words_per_row` u64 words, column `c` of a row is bit
+// `c % 64` of word `c / 64`. Bits past the width in the last word of a row are
+// always zero. `tick` works on whole words at a time: the eight neighbours of
+// 64 cells are added up with bitwise adders and the rule is applied to the
+// resulting counts, which is far faster than visiting every cell.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::random::{self, Random};
+use crate::{rle, Cell, ParseRleError, ParseRuleError, Rule, Topology, Universe};
+
+const WORD_BITS: u32 = 64;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct PackedUniverse {
+    width: u32,
+    height: u32,
+    words_per_row: usize,
+    cells: Vec<u64>,
+    rule: Rule,
+    topology: Topology,
+}
+
+// Add one bit to each of the 64 bit-sliced counters held in `count`
+// (`count[i]` holds bit `i` of every counter)
+fn add(count: &mut [u64; 4], bits: u64) {
+    let mut carry = bits;
+    for digit in count.iter_mut() {
+        let next_carry = *digit & carry;
+        *digit ^= carry;
+        carry = next_carry;
+    }
+}
+
+// Bits set where the bit-sliced counters are equal to `n`
+fn equals(count: &[u64; 4], n: u8) -> u64 {
+    count.iter().enumerate().fold(!0, |mask, (i, &digit)| {
+        if n & (1 << i) != 0 {
+            mask & digit
+        } else {
+            mask & !digit
+        }
+    })
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PackedUniverse {
+    // Index of the word holding a given cell and the bit of the cell in it
+    fn get_position(&self, row: u32, column: u32) -> (usize, u32) {
+        let word = row as usize * self.words_per_row + (column / WORD_BITS) as usize;
+        (word, column % WORD_BITS)
+    }
+
+    fn is_set(&self, row: u32, column: u32) -> bool {
+        let (word, bit) = self.get_position(row, column);
+        self.cells[word] & (1 << bit) != 0
+    }
+
+    fn row(&self, row: u32) -> &[u64] {
+        let start = row as usize * self.words_per_row;
+        &self.cells[start..start + self.words_per_row]
+    }
+
+    // Mask of the bits in the last word of a row that are inside the universe
+    fn last_word_mask(&self) -> u64 {
+        match self.width % WORD_BITS {
+            0 => !0,
+            used => (1 << used) - 1,
+        }
+    }
+
+    // The row `row` reversed, so column `c` holds what was in column `width - 1 - c`
+    fn reversed_row(&self, row: u32) -> Vec<u64> {
+        let mut reversed = vec![0; self.words_per_row];
+        for column in (0..self.width).filter(|&column| self.is_set(row, column)) {
+            let target = self.width - 1 - column;
+            reversed[(target / WORD_BITS) as usize] |= 1 << (target % WORD_BITS);
+        }
+        reversed
+    }
+
+    // The value of the cell at `(row, column)` as the topology sees it, which
+    // may be just outside the grid
+    fn neighbour_bit(&self, row: i64, column: i64) -> u64 {
+        match self.topology.wrap(row, column, self.width, self.height) {
+            Some((row, column)) => self.is_set(row, column) as u64,
+            None => 0,
+        }
+    }
+
+    // The cells above, left and right of every cell in row `row` of the
+    // universe, given as three whole rows of words: the row itself and the row
+    // shifted so each cell sees its west and east neighbour.
+    fn neighbour_rows(&self, row: i64) -> [Vec<u64>; 3] {
+        // Where the first and last columns of this row end up tells whether
+        // the row exists, and whether it is mirrored by the topology
+        let middle = match self.topology.wrap(row, 0, self.width, self.height) {
+            None => vec![0; self.words_per_row],
+            Some((wrapped, 0)) => self.row(wrapped).to_vec(),
+            Some((wrapped, _)) => self.reversed_row(wrapped),
+        };
+        let last = self.words_per_row - 1;
+
+        // Value at column c - 1, column -1 comes from the topology
+        let mut west = vec![0; self.words_per_row];
+        for i in 0..self.words_per_row {
+            let carry = if i == 0 {
+                self.neighbour_bit(row, -1)
+            } else {
+                middle[i - 1] >> (WORD_BITS - 1)
+            };
+            west[i] = middle[i] << 1 | carry;
+        }
+        west[last] &= self.last_word_mask();
+
+        // Value at column c + 1, column `width` comes from the topology
+        let mut east = vec![0; self.words_per_row];
+        for i in 0..self.words_per_row {
+            let carry = if i == last {
+                0
+            } else {
+                middle[i + 1] << (WORD_BITS - 1)
+            };
+            east[i] = middle[i] >> 1 | carry;
+        }
+        let edge = self.width - 1;
+        east[(edge / WORD_BITS) as usize] |=
+            self.neighbour_bit(row, i64::from(self.width)) << (edge % WORD_BITS);
+
+        [west, middle, east]
+    }
+
+    // Public methods, exported to JavaScript.
+
+    /// Create a universe of the given size with every cell dead.
+    pub fn new(width: u32, height: u32) -> PackedUniverse {
+        let words_per_row = width.div_ceil(WORD_BITS).max(1) as usize;
+        PackedUniverse {
+            width: width.max(1),
+            height: height.max(1),
+            words_per_row,
+            cells: vec![0; words_per_row * height.max(1) as usize],
+            rule: Rule::default(),
+            topology: Topology::default(),
+        }
+    }
+
+    /// Create a universe from an RLE pattern, sized to fit the pattern.
+    pub fn from_rle(rle: &str) -> Result<PackedUniverse, ParseRleError> {
+        let pattern = rle::decode(rle)?;
+        let rule = pattern.rule.unwrap_or_default();
+        if rule.states() > 2 {
+            return Err(ParseRleError::new(
+                "Generations rules are not supported by PackedUniverse",
+            ));
+        }
+        let mut universe = PackedUniverse::new(pattern.width, pattern.height);
+        universe.rule = rule;
+        universe.set_cells(&pattern.cells);
+        Ok(universe)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Number of u64 words used for every row of cells.
+    pub fn words_per_row(&self) -> usize {
+        self.words_per_row
+    }
+
+    /// Pointer to the packed cells, `height * words_per_row` u64 words.
+    ///
+    /// Since wasm is little endian the buffer can also be read from JS as a
+    /// `Uint32Array` with `2 * words_per_row` words per row, where column `c`
+    /// is bit `c % 32` of word `c / 32`.
+    pub fn cells(&self) -> *const u64 {
+        self.cells.as_ptr()
+    }
+
+    pub fn is_alive(&self, row: u32, column: u32) -> bool {
+        self.is_set(row, column)
+    }
+
+    pub fn toggle_cell(&mut self, row: u32, column: u32) {
+        let (word, bit) = self.get_position(row, column);
+        self.cells[word] ^= 1 << bit;
+    }
+
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    /// Change the birth/survival rule from a rulestring like `B36/S23` or `23/36`.
+    ///
+    /// A bit per cell has no room for dying states, Generations rules are
+    /// rejected.
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), ParseRuleError> {
+        let rule: Rule = rule.parse()?;
+        if rule.states() > 2 {
+            return Err(ParseRuleError::new(
+                &rule.to_string(),
+                "Generations rules are not supported by PackedUniverse",
+            ));
+        }
+        self.rule = rule;
+        Ok(())
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    /// Sets the universe to a random state
+    pub fn randomize(&mut self) {
+        self.randomize_with_seed(random::random_seed(), 0.5);
+    }
+
+    /// Sets the universe to a random soup where each cell is alive with
+    /// probability `density`. Gives the same soup as `Universe` does for the
+    /// same seed and density.
+    pub fn randomize_with_seed(&mut self, seed: u64, density: f64) {
+        self.reset();
+        let mut random = Random::new(seed);
+        for row in 0..self.height {
+            for col in 0..self.width {
+                if random.next_f64() < density {
+                    self.toggle_cell(row, col);
+                }
+            }
+        }
+    }
+
+    /// Resets the universe to all dead cells
+    pub fn reset(&mut self) {
+        for word in self.cells.iter_mut() {
+            *word = 0;
+        }
+    }
+
+    // Compute the next generation of the universe
+    pub fn tick(&mut self) {
+        let mut next = vec![0; self.cells.len()];
+        let last_word_mask = self.last_word_mask();
+
+        // Masks of the neighbour counts that give a live cell next generation
+        let births: Vec<u8> = (0..=8).filter(|&n| self.rule.is_born(n)).collect();
+        let survivals: Vec<u8> = (0..=8).filter(|&n| self.rule.survives(n)).collect();
+
+        let mut above = self.neighbour_rows(-1);
+        let mut current = self.neighbour_rows(0);
+        for row in 0..self.height {
+            let below = self.neighbour_rows(i64::from(row) + 1);
+            let cells = self.row(row);
+
+            for i in 0..self.words_per_row {
+                let mut count = [0; 4];
+                for rows in [&above, &below].iter() {
+                    add(&mut count, rows[0][i]);
+                    add(&mut count, rows[1][i]);
+                    add(&mut count, rows[2][i]);
+                }
+                add(&mut count, current[0][i]);
+                add(&mut count, current[2][i]);
+
+                let born = births.iter().fold(0, |mask, &n| mask | equals(&count, n));
+                let survive = survivals
+                    .iter()
+                    .fold(0, |mask, &n| mask | equals(&count, n));
+                let alive = cells[i];
+                next[row as usize * self.words_per_row + i] = (alive & survive) | (!alive & born);
+            }
+            next[(row as usize + 1) * self.words_per_row - 1] &= last_word_mask;
+
+            above = current;
+            current = below;
+        }
+        self.cells = next;
+    }
+
+    /// Encode the whole universe as an RLE pattern.
+    pub fn to_rle(&self) -> String {
+        rle::encode(self.width, &self.rule, &self.to_cells())
+    }
+}
+
+// Functions for use from Rust, not exported to JavaScript
+impl PackedUniverse {
+    /// Set cells to be alive in a universe by passing the row and column
+    /// of each cell as an array.
+    pub fn set_cells(&mut self, cells: &[(u32, u32)]) {
+        for (row, col) in cells.iter().cloned() {
+            let (word, bit) = self.get_position(row, col);
+            self.cells[word] |= 1 << bit;
+        }
+    }
+
+    /// Unpack the cells into one `Cell` per byte, the layout `Universe` uses.
+    pub fn to_cells(&self) -> Vec<Cell> {
+        let mut cells = Vec::with_capacity((self.width * self.height) as usize);
+        for row in 0..self.height {
+            for column in 0..self.width {
+                cells.push(if self.is_set(row, column) {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                });
+            }
+        }
+        cells
+    }
+}
+
+// Dying cells are left out and a Generations rule runs as its two state
+// version, since a bit per cell has no room for more states
+impl From<&Universe> for PackedUniverse {
+    fn from(universe: &Universe) -> PackedUniverse {
+        let mut packed = PackedUniverse::new(universe.width, universe.height);
+        packed.rule = universe.rule.with_states(2);
+        packed.topology = universe.topology;
+        for row in 0..universe.height {
+            for column in 0..universe.width {
+                if universe.cells[universe.get_index(row, column)] == Cell::Alive {
+                    packed.set_cells(&[(row, column)]);
+                }
+            }
+        }
+        packed
+    }
+}
@@ -0,0 +1,127 @@
+// Patterns to place into a universe.
+//
+// A pattern is a set of live cells, given as offsets from the top left corner
+// of a `width` x `height` box. Rotating or mirroring a pattern turns the box
+// and the cells with it, translating moves the cells within the box, so a
+// translated pattern lands further down or right when it is placed. Every
+// method returns a new pattern, which keeps chaining them easy from
+// JavaScript.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{formats, rle, Error, ParseRleError, RlePattern, Transform};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    width: u32,
+    height: u32,
+    cells: Vec<(i64, i64)>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Pattern {
+    /// Decode a pattern from an RLE string.
+    pub fn from_rle(rle: &str) -> Result<Pattern, ParseRleError> {
+        Ok(Pattern::from(rle::decode(rle)?))
+    }
+
+    /// Decode a pattern in any of the supported formats.
+    pub fn parse(text: &str) -> Result<Pattern, Error> {
+        Ok(Pattern::from(formats::decode(text)?))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The number of live cells.
+    pub fn population(&self) -> u32 {
+        self.cells.len() as u32
+    }
+
+    /// The pattern turned or mirrored by `transform`.
+    pub fn transform(&self, transform: Transform) -> Pattern {
+        let (width, height) = transform.size(self.width, self.height);
+        Pattern {
+            width,
+            height,
+            cells: self
+                .cells
+                .iter()
+                .map(|&(row, column)| transform.apply_offset(row, column, self.width, self.height))
+                .collect(),
+        }
+    }
+
+    /// The pattern turned clockwise by `quarter_turns` quarter turns, or
+    /// counterclockwise if it is negative.
+    pub fn rotate(&self, quarter_turns: i32) -> Pattern {
+        self.transform(match quarter_turns.rem_euclid(4) {
+            0 => Transform::Identity,
+            1 => Transform::Rotate90,
+            2 => Transform::Rotate180,
+            _ => Transform::Rotate270,
+        })
+    }
+
+    /// The pattern mirrored left to right.
+    pub fn flip_horizontal(&self) -> Pattern {
+        self.transform(Transform::FlipHorizontal)
+    }
+
+    /// The pattern mirrored top to bottom.
+    pub fn flip_vertical(&self) -> Pattern {
+        self.transform(Transform::FlipVertical)
+    }
+
+    /// The pattern with every cell moved down by `rows` and right by
+    /// `columns`, which may be negative.
+    pub fn translate(&self, rows: i32, columns: i32) -> Pattern {
+        Pattern {
+            width: self.width,
+            height: self.height,
+            cells: self
+                .cells
+                .iter()
+                .map(|&(row, column)| (row + i64::from(rows), column + i64::from(columns)))
+                .collect(),
+        }
+    }
+}
+
+impl Pattern {
+    /// A `width` x `height` pattern with live cells at the given offsets.
+    pub fn new(width: u32, height: u32, cells: Vec<(i64, i64)>) -> Pattern {
+        Pattern {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// The `(row, column)` offsets of the live cells.
+    pub fn cells(&self) -> &[(i64, i64)] {
+        &self.cells
+    }
+}
+
+// Cells in a dying state of a Generations rule are left out
+impl From<RlePattern> for Pattern {
+    fn from(pattern: RlePattern) -> Pattern {
+        Pattern {
+            width: pattern.width,
+            height: pattern.height,
+            cells: pattern
+                .cells
+                .iter()
+                .map(|&(row, column)| (i64::from(row), i64::from(column)))
+                .collect(),
+        }
+    }
+}
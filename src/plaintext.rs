@@ -0,0 +1,57 @@
+// Reading and writing patterns in the plaintext `.cells` format.
+//
+// Lines starting with `!` are comments, every other line is a row of cells
+// where `.` is a dead cell and `O` a live one (some files use `*`). Rows can
+// stop early, the rest of the row is dead. See
+// https://conwaylife.com/wiki/Plaintext
+
+use crate::formats::{Format, ParsePatternError};
+use crate::{write_rows, Cell, RlePattern};
+
+/// Decode a plaintext pattern into its size and live cells.
+pub fn decode(text: &str) -> Result<RlePattern, ParsePatternError> {
+    let mut cells = Vec::new();
+    let (mut width, mut height) = (0, 0);
+
+    for line in text.trim_end().lines().map(str::trim_end) {
+        if line.starts_with('!') {
+            continue;
+        }
+        for (column, c) in line.chars().enumerate() {
+            match c {
+                '.' => {}
+                'O' | '*' => cells.push((height, column as u32)),
+                c => {
+                    return Err(ParsePatternError::new(
+                        Format::Plaintext,
+                        format!("unexpected character '{}'", c),
+                    ))
+                }
+            }
+        }
+        width = width.max(line.chars().count() as u32);
+        height += 1;
+    }
+
+    Ok(RlePattern {
+        width,
+        height,
+        rule: None,
+        cells,
+        dying: Vec::new(),
+    })
+}
+
+/// Encode a `width` wide grid of cells as a plaintext pattern.
+pub fn encode(width: u32, cells: &[Cell]) -> String {
+    let mut output = String::new();
+    // Writing to a `String` can not fail
+    let _ = write_rows(&mut output, width, cells, |cell| {
+        if cell == Cell::Alive {
+            'O'
+        } else {
+            '.'
+        }
+    });
+    output
+}
@@ -0,0 +1,53 @@
+// Seeded pseudo random numbers for filling universes with random soups.
+//
+// This is SplitMix64, which is tiny, fast and good enough for soups. The same
+// seed always gives the same sequence on every platform, so a seed is all that
+// is needed to recreate a soup.
+
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0.0..1.0`
+    pub fn next_f64(&mut self) -> f64 {
+        // The top 53 bits fill the mantissa of an f64 exactly
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// A seed for when the caller did not pick one.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub fn random_seed() -> u64 {
+    // Math.random() has at most 53 random bits, take two to fill 64
+    let high = (js_sys::Math::random() * f64::from(u32::MAX)) as u64;
+    let low = (js_sys::Math::random() * f64::from(u32::MAX)) as u64;
+    high << 32 | low
+}
+
+/// A seed for when the caller did not pick one.
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+pub fn random_seed() -> u64 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // `RandomState` is randomly keyed by the OS, mix in the time for good measure
+    let mut hasher = RandomState::new().build_hasher();
+    if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        hasher.write_u128(now.as_nanos());
+    }
+    hasher.finish()
+}
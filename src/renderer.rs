@@ -0,0 +1,183 @@
+// Drawing a universe into an RGBA pixel buffer.
+//
+// Every cell is a `cell_size` x `cell_size` square of its color, with grid
+// lines `grid_width` pixels wide between the cells and around the edge. The
+// buffer is four bytes per pixel, red, green, blue and alpha, row by row, the
+// layout of `ImageData`, so JavaScript can wrap it in an `ImageData` and draw
+// it with a single `putImageData`.
+//
+// Colors are given as `0xRRGGBBAA`. The dying states of a Generations rule
+// fade from the alive color to the dead color.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{Error, Universe};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Renderer {
+    cell_size: u32,
+    grid_width: u32,
+    dead_color: [u8; 4],
+    alive_color: [u8; 4],
+    grid_color: [u8; 4],
+    // Size of the last rendered image, in pixels
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+// Mix `numerator / denominator` of `to` into `from`
+fn blend(from: [u8; 4], to: [u8; 4], numerator: u32, denominator: u32) -> [u8; 4] {
+    let mut color = from;
+    for (channel, &target) in color.iter_mut().zip(to.iter()) {
+        let (a, b) = (u32::from(*channel), u32::from(target));
+        *channel = ((a * (denominator - numerator) + b * numerator) / denominator) as u8;
+    }
+    color
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Renderer {
+    /// A renderer drawing cells `cell_size` pixels wide with grid lines
+    /// `grid_width` pixels wide, 0 for no grid. Dead cells are white, live
+    /// cells black and the grid light grey.
+    pub fn new(cell_size: u32, grid_width: u32) -> Renderer {
+        Renderer {
+            cell_size,
+            grid_width,
+            dead_color: [0xff, 0xff, 0xff, 0xff],
+            alive_color: [0x00, 0x00, 0x00, 0xff],
+            grid_color: [0xcc, 0xcc, 0xcc, 0xff],
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+        }
+    }
+
+    pub fn set_cell_size(&mut self, cell_size: u32) {
+        self.cell_size = cell_size;
+    }
+
+    pub fn set_grid_width(&mut self, grid_width: u32) {
+        self.grid_width = grid_width;
+    }
+
+    pub fn set_dead_color(&mut self, rgba: u32) {
+        self.dead_color = rgba.to_be_bytes();
+    }
+
+    pub fn set_alive_color(&mut self, rgba: u32) {
+        self.alive_color = rgba.to_be_bytes();
+    }
+
+    pub fn set_grid_color(&mut self, rgba: u32) {
+        self.grid_color = rgba.to_be_bytes();
+    }
+
+    /// Draw `universe` into the pixel buffer, resizing it to fit.
+    ///
+    /// Fails without touching the last image if the image would be bigger
+    /// than a pixel buffer can hold.
+    pub fn render(&mut self, universe: &Universe) -> Result<(), Error> {
+        // The side of the image in pixels, `cells` cells and the grid lines
+        // around them
+        let side = |cells: u32| {
+            self.cell_size
+                .checked_add(self.grid_width)?
+                .checked_mul(cells)?
+                .checked_add(self.grid_width)
+        };
+        let (width, height) = match (side(universe.width()), side(universe.height())) {
+            (Some(width), Some(height)) => (width, height),
+            _ => return Err(Error::ImageTooBig),
+        };
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(4))
+            .ok_or(Error::ImageTooBig)?;
+        self.pixels
+            .try_reserve(len.saturating_sub(self.pixels.len()))
+            .map_err(|_| Error::ImageTooBig)?;
+
+        let columns = universe.width() as usize;
+        let (cell_size, grid_width) = (self.cell_size as usize, self.grid_width as usize);
+        let step = cell_size + grid_width;
+        self.width = width;
+        self.height = height;
+        let stride = self.width as usize * 4;
+
+        // The grid color everywhere, then the cells on top
+        self.pixels.clear();
+        self.pixels.resize(len, 0);
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&self.grid_color);
+        }
+
+        let states = u32::from(universe.rule.states());
+        let palette: Vec<[u8; 4]> = (0..states)
+            .map(|state| match state {
+                0 => self.dead_color,
+                _ => blend(self.alive_color, self.dead_color, state - 1, states - 1),
+            })
+            .collect();
+
+        for (row, cells) in universe.get_cells().chunks(columns.max(1)).enumerate() {
+            // Draw the first line of pixels of the row, then copy it down
+            let top = grid_width + row * step;
+            let line = &mut self.pixels[top * stride..(top + 1) * stride];
+            for (column, cell) in cells.iter().enumerate() {
+                let left = (grid_width + column * step) * 4;
+                let color = palette
+                    .get(usize::from(cell.state()))
+                    .unwrap_or(&self.alive_color);
+                for pixel in line[left..left + cell_size * 4].chunks_exact_mut(4) {
+                    pixel.copy_from_slice(color);
+                }
+            }
+            for y in 1..cell_size {
+                self.pixels
+                    .copy_within(top * stride..(top + 1) * stride, (top + y) * stride);
+            }
+        }
+        Ok(())
+    }
+
+    /// Width of the last rendered image in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the last rendered image in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The pixels of the last rendered image, `width() * height() * 4`
+    /// bytes.
+    pub fn pixels(&self) -> *const u8 {
+        self.pixels.as_ptr()
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Renderer {
+        Renderer::new(5, 1)
+    }
+}
+
+impl Renderer {
+    /// Get the pixels of the last rendered image.
+    pub fn get_pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// The color of the pixel at `(x, y)` in the last rendered image, as
+    /// `0xRRGGBBAA`.
+    pub fn pixel(&self, x: u32, y: u32) -> u32 {
+        let start = (y as usize * self.width as usize + x as usize) * 4;
+        let mut rgba = [0; 4];
+        rgba.copy_from_slice(&self.pixels[start..start + 4]);
+        u32::from_be_bytes(rgba)
+    }
+}
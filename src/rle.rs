@@ -0,0 +1,350 @@
+// Reading and writing patterns in the run length encoded (RLE) format.
+//
+// An RLE file has optional `#` comment lines, a `x = 3, y = 3, rule = B3/S23`
+// header and a body of runs like `bo$2bo$3o!` where `b` is a dead cell, `o` a
+// live cell, `$` ends a row and `!` ends the pattern. Any item can be prefixed
+// with a run count. See https://conwaylife.com/wiki/Run_Length_Encoded
+//
+// Patterns for Generations rules use `.` for a dead cell and the letters `A`
+// to `X` for states 1 to 24. Higher states get a lowercase prefix, `pA` to
+// `pX` are states 25 to 48, `qA` the next 24 and so on.
+
+use std::fmt;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{BoundingBox, Cell, ParseRuleError, Rule, MAX_CELLS};
+
+// RLE lines should not be longer than this
+const MAX_LINE_LENGTH: usize = 70;
+
+/// A pattern decoded from an RLE string, or any of the other supported formats.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RlePattern {
+    pub width: u32,
+    pub height: u32,
+    pub rule: Option<Rule>,
+    /// `(row, column)` of every live cell, relative to the top left corner
+    pub cells: Vec<(u32, u32)>,
+    /// `(row, column, state)` of every cell in a dying state of a
+    /// Generations rule
+    pub dying: Vec<(u32, u32, u8)>,
+}
+
+/// Error returned when an RLE string can not be decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseRleError {
+    reason: String,
+}
+
+impl ParseRleError {
+    pub(crate) fn new(reason: impl Into<String>) -> ParseRleError {
+        ParseRleError {
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseRleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid RLE pattern: {}", self.reason)
+    }
+}
+
+impl std::error::Error for ParseRleError {}
+
+impl From<ParseRuleError> for ParseRleError {
+    fn from(err: ParseRuleError) -> ParseRleError {
+        ParseRleError::new(err.to_string())
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl From<ParseRleError> for JsValue {
+    fn from(err: ParseRleError) -> JsValue {
+        js_sys::Error::new(&err.to_string()).into()
+    }
+}
+
+// Parse the `x = 3, y = 3, rule = B3/S23` header line
+fn parse_header(line: &str) -> Result<(u32, u32, Option<Rule>), ParseRleError> {
+    let (mut width, mut height, mut rule) = (None, None, None);
+
+    for field in line.split(',') {
+        let mut pair = field.splitn(2, '=');
+        let key = pair.next().unwrap_or("").trim();
+        let value = pair
+            .next()
+            .ok_or_else(|| {
+                ParseRleError::new(format!("header field \"{}\" has no value", field.trim()))
+            })?
+            .trim();
+
+        let size = || {
+            value
+                .parse::<u32>()
+                .map_err(|_| ParseRleError::new(format!("bad pattern size \"{}\"", value)))
+        };
+        match key {
+            "x" => width = Some(size()?),
+            "y" => height = Some(size()?),
+            "rule" => rule = Some(value.parse()?),
+            // Unknown fields are allowed by the format, ignore them
+            _ => {}
+        }
+    }
+
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width, height, rule)),
+        _ => Err(ParseRleError::new("header must give both x and y")),
+    }
+}
+
+// Fail if `run` more cells would take a pattern past the most live cells a
+// universe can hold
+fn check_cell_count(cells: usize, run: u32) -> Result<(), ParseRleError> {
+    if cells as u64 + u64::from(run) > MAX_CELLS {
+        return Err(ParseRleError::new(
+            "pattern has more cells than a universe can hold",
+        ));
+    }
+    Ok(())
+}
+
+// Move `position` on by `by` cells, failing past the last row or column a
+// pattern can have
+fn advance(position: u32, by: u32) -> Result<u32, ParseRleError> {
+    position
+        .checked_add(by)
+        .ok_or_else(|| ParseRleError::new("pattern is too big"))
+}
+
+/// Decode an RLE string into its size, rule and live cells. Fails on patterns
+/// with more cells than a `Universe` can hold.
+pub fn decode(rle: &str) -> Result<RlePattern, ParseRleError> {
+    let mut header = None;
+    let mut cells = Vec::new();
+    let mut dying = Vec::new();
+    let (mut row, mut column) = (0, 0);
+    let (mut width, mut height) = (0, 0);
+    let mut count: Option<u32> = None;
+    // Multiple of 24 added by a `p`-`y` state prefix
+    let mut prefix: Option<u32> = None;
+    let mut finished = false;
+
+    for line in rle.lines().map(str::trim) {
+        if finished {
+            break;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if header.is_none() && cells.is_empty() && line.starts_with('x') {
+            header = Some(parse_header(line)?);
+            continue;
+        }
+
+        for c in line.chars() {
+            if prefix.is_some() && !c.is_ascii_uppercase() {
+                return Err(ParseRleError::new(
+                    "state prefix is not followed by a state",
+                ));
+            }
+            match c {
+                '0'..='9' => {
+                    let digit = c.to_digit(10).unwrap();
+                    let run = count
+                        .unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(digit))
+                        .ok_or_else(|| ParseRleError::new("run count is too large"))?;
+                    count = Some(run);
+                    continue;
+                }
+                'b' | '.' => column = advance(column, count.unwrap_or(1))?,
+                'o' => {
+                    let end = advance(column, count.unwrap_or(1))?;
+                    check_cell_count(cells.len() + dying.len(), end - column)?;
+                    cells.extend((column..end).map(|column| (row, column)));
+                    column = end;
+                }
+                'p'..='y' => {
+                    prefix = Some(c as u32 - 'o' as u32);
+                    continue;
+                }
+                'A'..='X' => {
+                    let state = prefix.take().unwrap_or(0) * 24 + c as u32 - 'A' as u32 + 1;
+                    if state > u32::from(u8::MAX) {
+                        return Err(ParseRleError::new(format!("state {} is too high", state)));
+                    }
+                    let end = advance(column, count.unwrap_or(1))?;
+                    check_cell_count(cells.len() + dying.len(), end - column)?;
+                    if state == 1 {
+                        cells.extend((column..end).map(|column| (row, column)));
+                    } else {
+                        dying.extend((column..end).map(|column| (row, column, state as u8)));
+                    }
+                    column = end;
+                }
+                '$' => {
+                    row = advance(row, count.unwrap_or(1))?;
+                    column = 0;
+                }
+                '!' => {
+                    finished = true;
+                    break;
+                }
+                c if c.is_whitespace() => {}
+                c => return Err(ParseRleError::new(format!("unexpected character '{}'", c))),
+            }
+            count = None;
+            width = width.max(column);
+            height = height.max(if column > 0 { advance(row, 1)? } else { row });
+        }
+    }
+
+    if count.is_some() || prefix.is_some() {
+        return Err(ParseRleError::new("run count is not followed by a cell"));
+    }
+
+    // Trust the header for the size but grow it if some cells lie outside
+    let (width, height, rule) = match header {
+        Some((x, y, rule)) => (width.max(x), height.max(y), rule),
+        None => (width, height, None),
+    };
+
+    Ok(RlePattern {
+        width,
+        height,
+        rule,
+        cells,
+        dying,
+    })
+}
+
+// Appends RLE items to the output, wrapping lines at `MAX_LINE_LENGTH`
+struct RleWriter {
+    output: String,
+    line_length: usize,
+}
+
+impl RleWriter {
+    fn push(&mut self, count: u64, tag: &str) {
+        let item = if count == 1 {
+            tag.to_string()
+        } else {
+            format!("{}{}", count, tag)
+        };
+        if self.line_length + item.len() > MAX_LINE_LENGTH {
+            self.output.push('\n');
+            self.line_length = 0;
+        }
+        self.line_length += item.len();
+        self.output.push_str(&item);
+    }
+}
+
+// The tag for a cell, `b`/`o` for two state rules and `.`/`A`/`B`/... for
+// Generations rules
+fn tag(cell: Cell, states: u8) -> String {
+    match (cell, states) {
+        (Cell::Dead, 2) => "b".to_string(),
+        (Cell::Alive, 2) => "o".to_string(),
+        (Cell::Dead, _) => ".".to_string(),
+        _ => {
+            let state = cell.state() - 1;
+            let letter = (b'A' + state % 24) as char;
+            match state / 24 {
+                0 => letter.to_string(),
+                prefix => format!("{}{}", (b'o' + prefix) as char, letter),
+            }
+        }
+    }
+}
+
+/// Encode a `width` wide grid of cells as an RLE string with a header.
+pub fn encode(width: u32, rule: &Rule, cells: &[Cell]) -> String {
+    let height = cells.len() as u32 / width.max(1);
+    let mut writer = RleWriter {
+        output: format!("x = {}, y = {}, rule = {}\n", width, height, rule),
+        line_length: 0,
+    };
+
+    // Row breaks are held back so that empty rows and trailing empty rows
+    // collapse into a single `n$` or disappear before the `!`.
+    let mut pending_rows = 0;
+    for line in cells.chunks(width.max(1) as usize) {
+        // Dead cells at the end of a row are implied by the `$`
+        let length = line
+            .iter()
+            .rposition(|&cell| cell != Cell::Dead)
+            .map_or(0, |last| last + 1);
+        if length > 0 && pending_rows > 0 {
+            writer.push(pending_rows, "$");
+            pending_rows = 0;
+        }
+
+        let mut cells = line[..length].iter().peekable();
+        while let Some(&cell) = cells.next() {
+            let mut run = 1;
+            while cells.peek() == Some(&&cell) {
+                cells.next();
+                run += 1;
+            }
+            writer.push(run, &tag(cell, rule.states()));
+        }
+        pending_rows += 1;
+    }
+    writer.push(1, "!");
+    writer.output.push('\n');
+    writer.output
+}
+
+/// Encode the bounding box of some live cells on an unbounded plane as an
+/// RLE string with a header.
+pub fn encode_cells(rule: &Rule, cells: &[(i64, i64)]) -> String {
+    let bounds = match BoundingBox::around(cells) {
+        Some(bounds) => bounds,
+        None => return encode(0, rule, &[]),
+    };
+    let mut writer = RleWriter {
+        output: format!(
+            "x = {}, y = {}, rule = {}\n",
+            bounds.width(),
+            bounds.height(),
+            rule
+        ),
+        line_length: 0,
+    };
+
+    // The cells are written row by row straight from the sorted list, the
+    // bounding box can be far too big for a grid. Differences go through
+    // `u64` since they can be bigger than `i64::MAX`.
+    let mut sorted = cells.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    let (mut row, mut column) = (bounds.top, bounds.left);
+    let mut cells = sorted.into_iter().peekable();
+    while let Some((next_row, next_column)) = cells.next() {
+        if next_row != row {
+            writer.push((next_row as u64).wrapping_sub(row as u64), "$");
+            row = next_row;
+            column = bounds.left;
+        }
+        if next_column != column {
+            writer.push((next_column as u64).wrapping_sub(column as u64), "b");
+        }
+
+        let mut run = 1;
+        while cells.peek() == Some(&(row, next_column.wrapping_add(run as i64))) {
+            cells.next();
+            run += 1;
+        }
+        writer.push(run, "o");
+        column = next_column.wrapping_add(run as i64);
+    }
+    writer.push(1, "!");
+    writer.output.push('\n');
+    writer.output
+}
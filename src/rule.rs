@@ -0,0 +1,237 @@
+// Birth/survival rules for the universe.
+//
+// A rule says how many live neighbours a dead cell needs to be born and how
+// many a live cell needs to survive. Rules are written as rulestrings, either in
+// the `B3/S23` notation or the older `23/3` (survival/birth) notation.
+//
+// Rules of the Generations family, like Brian's Brain `B2/S/C3`, have more
+// than two states per cell. A live cell that does not survive starts dying
+// instead of going straight to dead: it goes through the states after alive
+// one generation at a time, and only alive cells count as neighbours.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::Cell;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rule {
+    // Bit `n` is set if a dead cell with `n` live neighbours is born
+    birth: u16,
+    // Bit `n` is set if a live cell with `n` live neighbours survives
+    survival: u16,
+    // Number of cell states including dead and alive, 2 for Life-like rules
+    states: u8,
+}
+
+impl Rule {
+    /// Conway's Game of Life, `B3/S23`.
+    pub const CONWAY: Rule = Rule {
+        birth: 1 << 3,
+        survival: 1 << 2 | 1 << 3,
+        states: 2,
+    };
+
+    /// Build a rule from the neighbour counts that cause a birth and the
+    /// neighbour counts that let a live cell survive.
+    ///
+    /// Counts above 8 are ignored since a cell only has 8 neighbours.
+    pub fn new(birth: &[u8], survival: &[u8]) -> Rule {
+        Rule {
+            birth: to_mask(birth),
+            survival: to_mask(survival),
+            states: 2,
+        }
+    }
+
+    /// The same rule with `states` states per cell, making it a Generations
+    /// rule if there are more than two. Fewer than two states are taken as two.
+    pub fn with_states(self, states: u8) -> Rule {
+        Rule {
+            states: states.max(2),
+            ..self
+        }
+    }
+
+    /// The number of states a cell can be in, counting dead and alive.
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    /// Does a dead cell with `live_neighbours` live neighbours come alive?
+    pub fn is_born(&self, live_neighbours: u8) -> bool {
+        self.birth & (1 << live_neighbours) != 0
+    }
+
+    /// Does a live cell with `live_neighbours` live neighbours stay alive?
+    pub fn survives(&self, live_neighbours: u8) -> bool {
+        self.survival & (1 << live_neighbours) != 0
+    }
+
+    /// Why the rule can not run on an unbounded plane, `None` if it can.
+    /// Dead cells with no neighbours being born (B0) would fill the plane in
+    /// one generation, and only live cells are kept so there is no room for
+    /// the dying states of a Generations rule.
+    pub(crate) fn unbounded_error(&self) -> Option<&'static str> {
+        if self.is_born(0) {
+            Some("rules with B0 are not supported on an unbounded plane")
+        } else if self.states() > 2 {
+            Some("Generations rules are not supported on an unbounded plane")
+        } else {
+            None
+        }
+    }
+
+    /// The state of `cell` in the next generation.
+    pub fn next(&self, cell: Cell, live_neighbours: u8) -> Cell {
+        match cell {
+            Cell::Dead if self.is_born(live_neighbours) => Cell::Alive,
+            Cell::Dead => Cell::Dead,
+            Cell::Alive if self.survives(live_neighbours) => Cell::Alive,
+            // Live cells that do not survive and dying cells move on to the
+            // next state, the state after the last one is dead
+            _ if cell.state() + 1 < self.states => Cell::from_state(cell.state() + 1),
+            _ => Cell::Dead,
+        }
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Rule {
+        Rule::CONWAY
+    }
+}
+
+fn to_mask(counts: &[u8]) -> u16 {
+    counts
+        .iter()
+        .filter(|&&n| n <= 8)
+        .fold(0, |mask, &n| mask | 1 << n)
+}
+
+/// Error returned when a rulestring can not be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseRuleError {
+    rule: String,
+    reason: &'static str,
+}
+
+impl ParseRuleError {
+    pub(crate) fn new(rule: &str, reason: &'static str) -> ParseRuleError {
+        ParseRuleError {
+            rule: rule.to_string(),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for ParseRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid rule \"{}\": {}", self.rule, self.reason)
+    }
+}
+
+impl std::error::Error for ParseRuleError {}
+
+// Lets exported functions return the error, it shows up as a thrown `Error` in JS
+#[cfg(feature = "wasm")]
+impl From<ParseRuleError> for JsValue {
+    fn from(err: ParseRuleError) -> JsValue {
+        js_sys::Error::new(&err.to_string()).into()
+    }
+}
+
+// Parse the digits of one half of a rulestring into a neighbour count mask
+fn parse_counts(digits: &str) -> Option<u16> {
+    let mut mask = 0;
+    for c in digits.chars() {
+        match c.to_digit(10) {
+            Some(n) if n <= 8 => mask |= 1 << n,
+            _ => return None,
+        }
+    }
+    Some(mask)
+}
+
+impl FromStr for Rule {
+    type Err = ParseRuleError;
+
+    /// Parse `B36/S23` style rulestrings (in any order, case insensitive)
+    /// as well as the older `23/36` survival/birth notation. A third part
+    /// gives the number of states of a Generations rule, `B2/S/C3` or `/2/3`.
+    fn from_str(s: &str) -> Result<Rule, ParseRuleError> {
+        let error = |reason| ParseRuleError::new(s, reason);
+
+        let parts: Vec<&str> = s.trim().split('/').map(str::trim).collect();
+        if parts.len() != 2 && parts.len() != 3 {
+            return Err(error("expected two or three parts separated by '/'"));
+        }
+
+        let has_prefix = |part: &str| part.starts_with(|c: char| "BbSsCc".contains(c));
+        let prefixed = parts.iter().filter(|part| has_prefix(part)).count();
+        let (birth, survival, states) = if prefixed == parts.len() {
+            // `B3/S23` or `B2/S/C3`, in any order
+            let (mut birth, mut survival, mut states) = (None, None, None);
+            for part in parts.iter() {
+                let (prefix, digits) = part.split_at(1);
+                let slot = match prefix {
+                    "B" | "b" => &mut birth,
+                    "S" | "s" => &mut survival,
+                    _ => &mut states,
+                };
+                if slot.replace(digits).is_some() {
+                    return Err(error("expected one B, one S and at most one C part"));
+                }
+            }
+            match (birth, survival) {
+                (Some(birth), Some(survival)) => (birth, survival, states),
+                _ => return Err(error("expected one B, one S and at most one C part")),
+            }
+        } else if prefixed == 0 {
+            // Without prefixes the notation is survival/birth, `23/3`, with
+            // the number of states last for Generations rules, `345/2/4`
+            (parts[1], parts[0], parts.get(2).cloned())
+        } else {
+            return Err(error("either all or no parts must have a B/S/C prefix"));
+        };
+
+        let birth =
+            parse_counts(birth).ok_or_else(|| error("neighbour counts must be digits 0-8"))?;
+        let survival =
+            parse_counts(survival).ok_or_else(|| error("neighbour counts must be digits 0-8"))?;
+        let states = match states {
+            Some(states) => states
+                .parse()
+                .ok()
+                .filter(|&states| states >= 2)
+                .ok_or_else(|| error("the number of states must be 2-255"))?,
+            None => 2,
+        };
+
+        Ok(Rule {
+            birth,
+            survival,
+            states,
+        })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B")?;
+        for n in (0..=8).filter(|&n| self.is_born(n)) {
+            write!(f, "{}", n)?;
+        }
+        write!(f, "/S")?;
+        for n in (0..=8).filter(|&n| self.survives(n)) {
+            write!(f, "{}", n)?;
+        }
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        Ok(())
+    }
+}
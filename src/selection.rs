@@ -0,0 +1,63 @@
+// A rectangle of cells picked out for the clipboard operations.
+//
+// The selection always lies inside the universe, selecting past an edge only
+// keeps the part inside. Copied cells come out as RLE, so they can be pasted
+// back into any universe or saved as a pattern file.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// The selected rectangle, `width` x `height` cells with its top left corner
+/// at `(row, column)`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Selection {
+    pub row: u32,
+    pub column: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Selection {
+    /// The part of the rectangle inside a `width` x `height` universe, `None`
+    /// if that is empty.
+    pub fn clip(
+        row: u32,
+        column: u32,
+        width: u32,
+        height: u32,
+        universe_width: u32,
+        universe_height: u32,
+    ) -> Option<Selection> {
+        let bottom = row.saturating_add(height).min(universe_height);
+        let right = column.saturating_add(width).min(universe_width);
+        if row >= bottom || column >= right {
+            return None;
+        }
+        Some(Selection {
+            row,
+            column,
+            width: right - column,
+            height: bottom - row,
+        })
+    }
+
+    /// The `(row, column)` of every selected cell, row by row.
+    pub fn cells(self) -> impl Iterator<Item = (u32, u32)> {
+        (self.row..self.row + self.height)
+            .flat_map(move |row| (self.column..self.column + self.width).map(move |c| (row, c)))
+    }
+}
+
+/// How pasted cells combine with the cells already there.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PasteMode {
+    /// Live cells of the pattern are added, nothing dies.
+    #[default]
+    Or,
+    /// Live cells of the pattern flip the cells under them.
+    Xor,
+    /// The pattern replaces everything under it, dead cells included.
+    Overwrite,
+}
@@ -0,0 +1,212 @@
+// An unbounded universe that only stores its live cells.
+//
+// Live cells are kept in a hash set of `(row, column)` coordinates, so the
+// plane has no edges and memory only grows with the population. Guns and
+// puffers can run forever without wrapping into themselves. Each tick counts
+// the neighbours of every live cell's surroundings, so the cost of a tick is
+// proportional to the population rather than to an area.
+
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{rle, ParseRleError, ParseRuleError, Rule};
+
+/// The smallest rectangle containing every live cell, `bottom` and `right`
+/// are exclusive.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub top: i64,
+    pub left: i64,
+    pub bottom: i64,
+    pub right: i64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl BoundingBox {
+    // The differences can be bigger than `i64::MAX`, but always fit a `u64`
+
+    pub fn width(&self) -> u64 {
+        (self.right as u64).wrapping_sub(self.left as u64)
+    }
+
+    pub fn height(&self) -> u64 {
+        (self.bottom as u64).wrapping_sub(self.top as u64)
+    }
+}
+
+impl BoundingBox {
+    /// The bounding box of some cells, `None` if there are no cells. Cells
+    /// in the last row or column of the plane, at `i64::MAX`, are left
+    /// outside since the exclusive edge can not go past them.
+    pub fn around<'a, I>(cells: I) -> Option<BoundingBox>
+    where
+        I: IntoIterator<Item = &'a (i64, i64)>,
+    {
+        cells.into_iter().fold(None, |bounds, &(row, column)| {
+            Some(match bounds {
+                None => BoundingBox {
+                    top: row,
+                    left: column,
+                    bottom: row.saturating_add(1),
+                    right: column.saturating_add(1),
+                },
+                Some(b) => BoundingBox {
+                    top: b.top.min(row),
+                    left: b.left.min(column),
+                    bottom: b.bottom.max(row.saturating_add(1)),
+                    right: b.right.max(column.saturating_add(1)),
+                },
+            })
+        })
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct SparseUniverse {
+    cells: HashSet<(i64, i64)>,
+    rule: Rule,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SparseUniverse {
+    /// An empty plane running Conway's rule.
+    pub fn new() -> SparseUniverse {
+        SparseUniverse {
+            cells: HashSet::new(),
+            rule: Rule::default(),
+        }
+    }
+
+    /// Create a plane from an RLE pattern, with the top left corner of the
+    /// pattern at the origin.
+    pub fn from_rle(rle: &str) -> Result<SparseUniverse, ParseRleError> {
+        let mut universe = SparseUniverse::new();
+        universe.load_rle(rle)?;
+        Ok(universe)
+    }
+
+    /// Replace every cell with an RLE pattern, with the top left corner of
+    /// the pattern at the origin. The rule is changed too if the pattern has
+    /// one.
+    pub fn load_rle(&mut self, rle: &str) -> Result<(), ParseRleError> {
+        let pattern = rle::decode(rle)?;
+        let rule = pattern.rule.unwrap_or(self.rule);
+        if let Some(reason) = rule.unbounded_error() {
+            return Err(ParseRleError::new(reason));
+        }
+
+        self.rule = rule;
+        self.cells = pattern
+            .cells
+            .iter()
+            .map(|&(row, column)| (i64::from(row), i64::from(column)))
+            .collect();
+        Ok(())
+    }
+
+    /// Encode the bounding box of all live cells as an RLE pattern.
+    pub fn to_rle(&self) -> String {
+        let cells: Vec<(i64, i64)> = self.cells.iter().cloned().collect();
+        rle::encode_cells(&self.rule, &cells)
+    }
+
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    /// Change the birth/survival rule from a rulestring like `B36/S23` or `23/36`.
+    /// B0 rules would fill the plane at once and Generations rules have
+    /// states only a bounded universe keeps, both are rejected.
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), ParseRuleError> {
+        let rule: Rule = rule.parse()?;
+        if let Some(reason) = rule.unbounded_error() {
+            return Err(ParseRuleError::new(&rule.to_string(), reason));
+        }
+        self.rule = rule;
+        Ok(())
+    }
+
+    /// Number of live cells.
+    pub fn population(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// The smallest rectangle containing every live cell, `undefined` if
+    /// every cell is dead.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        BoundingBox::around(&self.cells)
+    }
+
+    pub fn is_alive(&self, row: i64, column: i64) -> bool {
+        self.cells.contains(&(row, column))
+    }
+
+    pub fn toggle_cell(&mut self, row: i64, column: i64) {
+        if !self.cells.remove(&(row, column)) {
+            self.cells.insert((row, column));
+        }
+    }
+
+    /// Resets the universe to all dead cells
+    pub fn reset(&mut self) {
+        self.cells.clear();
+    }
+
+    // Compute the next generation of the universe
+    pub fn tick(&mut self) {
+        // Only cells next to a live cell can have live neighbours
+        let mut live_neighbours: HashMap<(i64, i64), u8> = HashMap::new();
+        for &(row, column) in self.cells.iter() {
+            for delta_row in [-1, 0, 1].iter().cloned() {
+                for delta_col in [-1, 0, 1].iter().cloned() {
+                    if delta_row == 0 && delta_col == 0 {
+                        continue;
+                    }
+                    let neighbour = (row.wrapping_add(delta_row), column.wrapping_add(delta_col));
+                    *live_neighbours.entry(neighbour).or_insert(0) += 1;
+                }
+            }
+        }
+
+        // Live cells without any live neighbours are not in the map
+        let mut next: HashSet<(i64, i64)> = self
+            .cells
+            .iter()
+            .filter(|&cell| !live_neighbours.contains_key(cell) && self.rule.survives(0))
+            .cloned()
+            .collect();
+        for (cell, count) in live_neighbours {
+            let alive = if self.cells.contains(&cell) {
+                self.rule.survives(count)
+            } else {
+                self.rule.is_born(count)
+            };
+            if alive {
+                next.insert(cell);
+            }
+        }
+        self.cells = next;
+    }
+}
+
+// Functions for use from Rust, not exported to JavaScript
+impl SparseUniverse {
+    /// Set cells to be alive by passing the row and column of each cell.
+    pub fn set_cells(&mut self, cells: &[(i64, i64)]) {
+        self.cells.extend(cells.iter().cloned());
+    }
+
+    /// `(row, column)` of every live cell, in no particular order.
+    pub fn live_cells(&self) -> Vec<(i64, i64)> {
+        self.cells.iter().cloned().collect()
+    }
+}
+
+impl Default for SparseUniverse {
+    fn default() -> SparseUniverse {
+        SparseUniverse::new()
+    }
+}
@@ -0,0 +1,60 @@
+// Population of the universe over time, for charting.
+
+use std::collections::VecDeque;
+
+// Generations kept by default
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// The population of the last generations, one value per generation.
+pub struct PopulationHistory {
+    populations: VecDeque<u32>,
+    // Generation of the first value
+    start: u32,
+    capacity: usize,
+}
+
+impl PopulationHistory {
+    pub fn new() -> PopulationHistory {
+        PopulationHistory {
+            populations: VecDeque::new(),
+            start: 0,
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+
+    /// Set the population of `generation`. Values for any later generations
+    /// are forgotten, they belong to a different future.
+    pub fn update(&mut self, generation: u32, population: u32) {
+        let keep = generation.wrapping_sub(self.start) as usize;
+        if generation < self.start || keep > self.populations.len() {
+            self.populations.clear();
+            self.start = generation;
+        } else {
+            self.populations.truncate(keep);
+        }
+
+        self.populations.push_back(population);
+        self.shrink();
+    }
+
+    /// Generation of the first value.
+    pub fn start(&self) -> u32 {
+        self.start
+    }
+
+    pub fn to_vec(&self) -> Vec<u32> {
+        self.populations.iter().cloned().collect()
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.shrink();
+    }
+
+    fn shrink(&mut self) {
+        while self.populations.len() > self.capacity {
+            self.populations.pop_front();
+            self.start += 1;
+        }
+    }
+}
@@ -0,0 +1,161 @@
+// Past generations of a universe, for stepping backwards in time.
+//
+// The state of the universe before every tick is kept in a ring buffer of
+// frames. Every `KEYFRAME_INTERVAL`th frame is a full copy of the cells, the
+// frames in between only hold the cells that differ from the frame before. Any
+// kept generation is rebuilt from the closest keyframe before it.
+
+use std::collections::VecDeque;
+
+use crate::Cell;
+
+// A full copy of the cells is kept this often
+const KEYFRAME_INTERVAL: usize = 32;
+
+// Generations kept by default
+const DEFAULT_CAPACITY: usize = 256;
+
+enum Frame {
+    Key(Vec<Cell>),
+    // `(index, cell)` for every cell that differs from the previous frame
+    Delta(Vec<(u32, Cell)>),
+}
+
+pub struct Timeline {
+    // Always starts with a keyframe
+    frames: VecDeque<Frame>,
+    // Generation of the first frame
+    first_generation: u32,
+    // The cells of the last frame
+    last: Vec<Cell>,
+    capacity: usize,
+}
+
+impl Timeline {
+    pub fn new() -> Timeline {
+        Timeline {
+            frames: VecDeque::new(),
+            first_generation: 0,
+            last: Vec::new(),
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+
+    /// The earliest generation that can be rebuilt.
+    pub fn first_generation(&self) -> Option<u32> {
+        if self.frames.is_empty() {
+            None
+        } else {
+            Some(self.first_generation)
+        }
+    }
+
+    /// Remember the cells of `generation`. Any frames from `generation` on
+    /// belong to a different future and are forgotten.
+    pub fn record(&mut self, generation: u32, cells: &[Cell]) {
+        self.truncate(generation);
+        if self.capacity == 0 {
+            return;
+        }
+        let next_generation = self.first_generation + self.frames.len() as u32;
+        if self.frames.is_empty() || generation != next_generation || cells.len() != self.last.len()
+        {
+            self.clear();
+            self.first_generation = generation;
+        }
+
+        let since_keyframe = self
+            .frames
+            .iter()
+            .rev()
+            .take_while(|frame| matches!(frame, Frame::Delta(_)))
+            .count();
+        let frame = if self.frames.is_empty() || since_keyframe + 1 >= KEYFRAME_INTERVAL {
+            Frame::Key(cells.to_vec())
+        } else {
+            Frame::Delta(
+                cells
+                    .iter()
+                    .zip(self.last.iter())
+                    .enumerate()
+                    .filter(|(_, (cell, last))| cell != last)
+                    .map(|(index, (&cell, _))| (index as u32, cell))
+                    .collect(),
+            )
+        };
+        self.frames.push_back(frame);
+        self.last = cells.to_vec();
+        self.shrink();
+    }
+
+    /// Rebuild the cells of `generation`, if it is still kept.
+    pub fn get(&self, generation: u32) -> Option<Vec<Cell>> {
+        let index = generation.checked_sub(self.first_generation)? as usize;
+        if index >= self.frames.len() {
+            return None;
+        }
+
+        // Walk back to the closest keyframe, then replay the deltas after it
+        let key = (0..=index)
+            .rev()
+            .find(|&i| matches!(self.frames[i], Frame::Key(_)))?;
+        let mut cells = match &self.frames[key] {
+            Frame::Key(cells) => cells.clone(),
+            Frame::Delta(_) => unreachable!(),
+        };
+        for frame in self.frames.range(key + 1..=index) {
+            if let Frame::Delta(changes) = frame {
+                for &(i, cell) in changes {
+                    cells[i as usize] = cell;
+                }
+            }
+        }
+        Some(cells)
+    }
+
+    /// Forget `generation` and every generation after it.
+    pub fn truncate(&mut self, generation: u32) {
+        let keep = generation.saturating_sub(self.first_generation) as usize;
+        if keep >= self.frames.len() {
+            return;
+        }
+        self.frames.truncate(keep);
+        match keep.checked_sub(1) {
+            Some(last) => self.last = self.get(self.first_generation + last as u32).unwrap(),
+            None => self.clear(),
+        }
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.shrink();
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.last.clear();
+    }
+
+    // Drop the oldest frames until the capacity is respected, turning the
+    // new first frame into a keyframe if needed
+    fn shrink(&mut self) {
+        while self.frames.len() > self.capacity {
+            let oldest = match self.frames.pop_front() {
+                Some(Frame::Key(cells)) => cells,
+                _ => unreachable!("the first frame is always a keyframe"),
+            };
+            self.first_generation += 1;
+
+            if let Some(Frame::Delta(changes)) = self.frames.front() {
+                let mut cells = oldest;
+                for &(i, cell) in changes {
+                    cells[i as usize] = cell;
+                }
+                self.frames[0] = Frame::Key(cells);
+            }
+        }
+        if self.frames.is_empty() {
+            self.last.clear();
+        }
+    }
+}
@@ -0,0 +1,64 @@
+// How the edges of the universe are joined together.
+//
+// Neighbour counting asks the topology where a cell just off the edge of the
+// grid really is. Depending on the topology it is either a cell on the other
+// side of the grid (possibly mirrored) or nothing at all, in which case it
+// counts as dead.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Topology {
+    /// Both pairs of edges wrap around, the classic game of life universe.
+    #[default]
+    Torus,
+    /// Nothing wraps, everything outside the grid is dead.
+    Bounded,
+    /// The left and right edges wrap around, the top and bottom are dead.
+    Cylinder,
+    /// The left and right edges wrap around, the top and bottom edges wrap
+    /// with a twist, so crossing them mirrors the column.
+    KleinBottle,
+    /// Both pairs of edges wrap with a twist, crossing the left or right edge
+    /// mirrors the row and crossing the top or bottom edge mirrors the column.
+    CrossSurface,
+}
+
+impl Topology {
+    /// Find the cell at `(row, column)`, which may lie outside a `width` x
+    /// `height` grid. Returns `None` if it is off an edge that does not wrap.
+    pub fn wrap(self, row: i64, column: i64, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (width, height) = (i64::from(width), i64::from(height));
+        let row_outside = row < 0 || row >= height;
+        let column_outside = column < 0 || column >= width;
+
+        let (wraps_rows, wraps_columns) = match self {
+            Topology::Torus | Topology::KleinBottle | Topology::CrossSurface => (true, true),
+            Topology::Cylinder => (false, true),
+            Topology::Bounded => (false, false),
+        };
+        if (row_outside && !wraps_rows) || (column_outside && !wraps_columns) {
+            return None;
+        }
+
+        // Which edges mirror the other coordinate when crossed
+        let (top_bottom_twisted, left_right_twisted) = match self {
+            Topology::KleinBottle => (true, false),
+            Topology::CrossSurface => (true, true),
+            _ => (false, false),
+        };
+
+        // Crossing a twisted edge twice mirrors the other coordinate back
+        let mut wrapped_row = row.rem_euclid(height);
+        let mut wrapped_column = column.rem_euclid(width);
+        if top_bottom_twisted && row.div_euclid(height) % 2 != 0 {
+            wrapped_column = width - 1 - wrapped_column;
+        }
+        if left_right_twisted && column.div_euclid(width) % 2 != 0 {
+            wrapped_row = height - 1 - wrapped_row;
+        }
+        Some((wrapped_row as u32, wrapped_column as u32))
+    }
+}
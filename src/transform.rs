@@ -0,0 +1,80 @@
+// Rotating and mirroring patterns.
+//
+// These are the eight symmetries of a square. Each one maps the cells of a
+// `width` x `height` pattern onto the cells of the transformed pattern, which
+// is `height` x `width` for the ones that turn the pattern on its side.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Transform {
+    /// Leave the pattern as it is.
+    #[default]
+    Identity,
+    /// Turn the pattern a quarter turn clockwise.
+    Rotate90,
+    /// Turn the pattern upside down.
+    Rotate180,
+    /// Turn the pattern a quarter turn counterclockwise.
+    Rotate270,
+    /// Mirror the pattern left to right.
+    FlipHorizontal,
+    /// Mirror the pattern top to bottom.
+    FlipVertical,
+    /// Mirror the pattern along the diagonal from the top left corner, rows
+    /// become columns.
+    Transpose,
+    /// Mirror the pattern along the diagonal from the top right corner.
+    AntiTranspose,
+}
+
+impl Transform {
+    /// Whether the transform swaps the width and height of a pattern.
+    pub fn swaps_sides(self) -> bool {
+        match self {
+            Transform::Rotate90
+            | Transform::Rotate270
+            | Transform::Transpose
+            | Transform::AntiTranspose => true,
+            Transform::Identity
+            | Transform::Rotate180
+            | Transform::FlipHorizontal
+            | Transform::FlipVertical => false,
+        }
+    }
+
+    /// The width and height of a `width` x `height` pattern after the
+    /// transform.
+    pub fn size(self, width: u32, height: u32) -> (u32, u32) {
+        if self.swaps_sides() {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Where the cell at `(row, column)` of a `width` x `height` pattern ends
+    /// up after the transform.
+    pub fn apply(self, row: u32, column: u32, width: u32, height: u32) -> (u32, u32) {
+        let (row, column) = self.apply_offset(i64::from(row), i64::from(column), width, height);
+        (row as u32, column as u32)
+    }
+
+    /// Like `apply`, for a cell that may lie outside of the pattern. It moves
+    /// the same way as it would if the pattern were big enough to hold it.
+    pub fn apply_offset(self, row: i64, column: i64, width: u32, height: u32) -> (i64, i64) {
+        let (last_row, last_column) = (i64::from(height) - 1, i64::from(width) - 1);
+        match self {
+            Transform::Identity => (row, column),
+            Transform::Rotate90 => (column, last_row - row),
+            Transform::Rotate180 => (last_row - row, last_column - column),
+            Transform::Rotate270 => (last_column - column, row),
+            Transform::FlipHorizontal => (row, last_column - column),
+            Transform::FlipVertical => (last_row - row, column),
+            Transform::Transpose => (column, row),
+            Transform::AntiTranspose => (last_column - column, last_row - row),
+        }
+    }
+}
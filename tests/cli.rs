@@ -0,0 +1,59 @@
+//! Tests for the `life` command line runner.
+
+use std::env;
+use std::fs;
+use std::process::Command;
+
+fn life(name: &str, pattern: &str, args: &[&str]) -> String {
+    let path = env::temp_dir().join(format!("life-test-{}.rle", name));
+    fs::write(&path, pattern).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_life"))
+        .arg(&path)
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn runs_generations() {
+    let glider = "x = 3, y = 3\nbo$2bo$3o!";
+    let output = life(
+        "glider",
+        glider,
+        &["-W", "8", "-H", "8", "-g", "4", "-t", "bounded"],
+    );
+    assert_eq!(output, "x = 8, y = 8, rule = B3/S23\n3$4bo$5bo$3b3o!\n");
+}
+
+#[test]
+fn prints_population() {
+    let blinker = "x = 3, y = 3\n$3o!";
+    let args = [
+        "-W",
+        "5",
+        "-H",
+        "5",
+        "-g",
+        "2",
+        "--population",
+        "-r",
+        "B3/S",
+    ];
+    let output = life("blinker", blinker, &args);
+    assert_eq!(output, "0 3\n1 2\n2 0\n");
+}
+
+#[test]
+fn converts_formats() {
+    let glider = "!Name: Glider\n.O\n..O\nOOO\n";
+    let output = life("plaintext", glider, &["-g", "0", "-f", "life106"]);
+    assert_eq!(output, "#Life 1.06\n1 0\n2 1\n0 2\n1 2\n2 2\n");
+
+    let output = life("glider-mc", glider, &["-g", "0", "-f", "mc"]);
+    assert!(output.starts_with("[M2] (wasm-game-of-life)\n#R B3/S23\n"));
+    let output = life("glider-from-mc", &output, &["-g", "0"]);
+    assert_eq!(output, "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n");
+}
@@ -1,6 +1,6 @@
 //! Test suite for the Web and headless browsers.
-
-#![cfg(target_arch = "wasm32")]
+//!
+//! The tests also run natively with `cargo test`, as plain `#[test]`s.
 
 extern crate wasm_bindgen_test;
 use wasm_bindgen_test::*;
@@ -32,7 +32,7 @@ pub fn expected_spaceship() -> Universe {
 #[wasm_bindgen_test] attribute in code block allows us to test our Rust-generated WebAssembly code 
                      and use wasm-pack test to test the WebAssembly code
 */
-#[wasm_bindgen_test]
+#[wasm_bindgen_test(unsupported = test)]
 pub fn test_tick() {
     // Let's create a smaller Universe with a small spaceship to test!
     let mut input_universe = input_spaceship();
@@ -45,3 +45,1020 @@ pub fn test_tick() {
     input_universe.tick();
     assert_eq!(&input_universe.get_cells(), &expected_universe.get_cells());
 }
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_rule_parsing() {
+    use wasm_game_of_life::Rule;
+
+    let conway: Rule = "B3/S23".parse().unwrap();
+    assert_eq!(conway, Rule::CONWAY);
+    assert_eq!("23/3".parse::<Rule>().unwrap(), Rule::CONWAY);
+    assert_eq!("s23/b3".parse::<Rule>().unwrap(), Rule::CONWAY);
+
+    let seeds: Rule = "B2/S".parse().unwrap();
+    assert_eq!(seeds, Rule::new(&[2], &[]));
+    assert_eq!(seeds.to_string(), "B2/S");
+
+    assert!("B3/S29".parse::<Rule>().is_err());
+    assert!("B3S23".parse::<Rule>().is_err());
+    assert!("B3/23".parse::<Rule>().is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_with_rule() {
+    // Under Seeds (B2/S) every live cell dies and the two cells next to the
+    // pair are born.
+    let mut universe = Universe::new();
+    universe.set_width(6);
+    universe.set_height(6);
+    universe.set_rule("B2/S").unwrap();
+    universe.set_cells(&[(2, 2), (2, 3)]);
+
+    let mut expected = Universe::new();
+    expected.set_width(6);
+    expected.set_height(6);
+    expected.set_cells(&[(1, 2), (1, 3), (3, 2), (3, 3)]);
+
+    universe.tick();
+    assert_eq!(&universe.get_cells(), &expected.get_cells());
+    assert!(universe.set_rule("not a rule").is_err());
+    assert_eq!(universe.rule(), "B2/S");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_rle_round_trip() {
+    let glider = "#N Glider\n#C A comment line\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!\n";
+    let universe = Universe::from_rle(glider).unwrap();
+    assert_eq!(universe.width(), 3);
+    assert_eq!(universe.height(), 3);
+
+    let mut expected = Universe::from_rle("x = 3, y = 3\n3b$3b$3b!").unwrap();
+    expected.set_cells(&[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]);
+    assert_eq!(&universe.get_cells(), &expected.get_cells());
+
+    assert_eq!(universe.to_rle(), "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n");
+
+    // Headers too big for a universe are an error, not a panic
+    assert!(Universe::from_rle("x = 100000, y = 100000\n!").is_err());
+    assert!(Universe::from_rle("x = 4294967295, y = 2\n!").is_err());
+    assert!(Universe::from_pattern("x = 100000, y = 100000\n!").is_err());
+
+    // and so are runs past the last row or column, or with too many cells
+    use wasm_game_of_life::{PasteMode, Pattern};
+    assert!(Universe::from_rle("4294967295b4294967295bo!").is_err());
+    assert!(Pattern::from_rle("4294967295b4294967295bo!").is_err());
+    let mut universe = Universe::from_rle("x = 3, y = 3\n!").unwrap();
+    assert!(universe.paste("4294967295$4294967295$o!", 0, 0, PasteMode::Or).is_err());
+    assert!(Universe::from_rle("x = 1, y = 1\n4000000000o!").is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_load_rle() {
+    let mut universe = input_spaceship();
+    universe.load_rle("x = 2, y = 2, rule = B36/S23\n2o$2o!").unwrap();
+    assert_eq!(universe.rule(), "B36/S23");
+
+    let mut expected = Universe::from_rle("x = 6, y = 6\n!").unwrap();
+    expected.set_cells(&[(2, 2), (2, 3), (3, 2), (3, 3)]);
+    assert_eq!(&universe.get_cells(), &expected.get_cells());
+
+    // Empty rows collapse into a single run
+    assert_eq!(universe.to_rle(), "x = 6, y = 6, rule = B36/S23\n2$2b2o$2b2o!\n");
+
+    assert!(universe.load_rle("x = 7, y = 1\n7o!").is_err());
+    assert!(universe.load_rle("x = 2, y = 2\n2o$2q!").is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_topologies() {
+    use wasm_game_of_life::Topology;
+
+    // A blinker lying along the top edge, its next phase crosses the edge
+    let blinker = "x = 5, y = 5\n3o!";
+    let tick_with = |topology| {
+        let mut universe = Universe::from_rle(blinker).unwrap();
+        universe.set_topology(topology);
+        universe.tick();
+        universe
+    };
+    let expected = |cells: &[(u32, u32)]| {
+        let mut universe = Universe::from_rle("x = 5, y = 5\n!").unwrap();
+        universe.set_cells(cells);
+        universe
+    };
+
+    let torus = tick_with(Topology::Torus);
+    assert_eq!(&torus.get_cells(), &expected(&[(4, 1), (0, 1), (1, 1)]).get_cells());
+
+    // Nothing is born above the top edge
+    let bounded = tick_with(Topology::Bounded);
+    assert_eq!(&bounded.get_cells(), &expected(&[(0, 1), (1, 1)]).get_cells());
+    let cylinder = tick_with(Topology::Cylinder);
+    assert_eq!(&cylinder.get_cells(), &expected(&[(0, 1), (1, 1)]).get_cells());
+
+    // Crossing the top edge of a Klein bottle mirrors the column
+    let klein = tick_with(Topology::KleinBottle);
+    assert_eq!(&klein.get_cells(), &expected(&[(4, 3), (0, 1), (1, 1)]).get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_packed_tick_matches_universe() {
+    use wasm_game_of_life::{PackedUniverse, Topology};
+
+    // Wider than one word so cells have neighbours in the next word
+    let mut soup = Vec::new();
+    for row in 0..9 {
+        for col in 0..70 {
+            if (row * 7 + col * 13 + row * col) % 5 < 2 {
+                soup.push((row, col));
+            }
+        }
+    }
+
+    let topologies = [
+        Topology::Torus,
+        Topology::Bounded,
+        Topology::Cylinder,
+        Topology::KleinBottle,
+        Topology::CrossSurface,
+    ];
+    for &topology in topologies.iter() {
+        for &rule in ["B3/S23", "B36/S125"].iter() {
+            let mut universe = Universe::from_rle("x = 70, y = 9\n!").unwrap();
+            universe.set_cells(&soup);
+            universe.set_topology(topology);
+            universe.set_rule(rule).unwrap();
+            let mut packed = PackedUniverse::from(&universe);
+
+            for _ in 0..4 {
+                universe.tick();
+                packed.tick();
+                assert_eq!(&packed.to_cells()[..], universe.get_cells());
+            }
+            assert_eq!(packed.to_rle(), universe.to_rle());
+        }
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_hashlife_glider() {
+    use wasm_game_of_life::HashLife;
+
+    let glider = "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n";
+    let mut hashlife = HashLife::from_rle(glider).unwrap();
+    hashlife.step_pow2(10).unwrap();
+
+    // A glider moves one cell diagonally every four generations
+    assert_eq!(hashlife.generation(), 1024);
+    assert_eq!(hashlife.population(), 5);
+    assert!(hashlife.is_alive(256, 257));
+    assert_eq!(hashlife.to_rle(), glider);
+
+    // Far beyond what ticking one generation at a time could reach
+    hashlife.step_pow2(40).unwrap();
+    assert_eq!(hashlife.generation(), (1 << 40) + 1024);
+    assert!(hashlife.is_alive((1 << 38) + 256, (1 << 38) + 257));
+
+    // Steps that would outgrow the plane fail instead of panicking
+    assert!(hashlife.step_pow2(59).is_err());
+    assert!(hashlife.step_pow2(255).is_err());
+    assert!(hashlife.step(u64::MAX).is_err());
+
+    let mut block = HashLife::from_rle("2o$2o!").unwrap();
+    block.step(u64::MAX).unwrap();
+    assert_eq!(block.generation(), u64::MAX);
+    assert!(block.step(1).is_err());
+    assert_eq!(block.population(), 4);
+
+    // and so do cells past the edge of the plane, setting none of them
+    let mut plane = HashLife::new();
+    assert!(plane.toggle_cell(i64::MAX, 0).is_err());
+    assert!(plane.set_cells(&[(0, 0), (0, i64::MIN)]).is_err());
+    assert_eq!(plane.population(), 0);
+    plane.toggle_cell((1 << 61) - 1, -(1 << 61)).unwrap();
+    assert_eq!(plane.population(), 1);
+
+    // A gun run for a long time still encodes without a huge grid
+    let gun = wasm_game_of_life::find_pattern("gosper-glider-gun").unwrap();
+    let mut hashlife = HashLife::from_rle(&gun.rle()).unwrap();
+    hashlife.step(1 << 16).unwrap();
+    let copy = HashLife::from_rle(&hashlife.to_rle()).unwrap();
+    assert_eq!(copy.population(), hashlife.population());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_hashlife_matches_universe() {
+    use wasm_game_of_life::{HashLife, Topology};
+
+    let r_pentomino = "x = 3, y = 3\nb2o$2o$bo!";
+    let mut universe = Universe::from_rle("x = 100, y = 100\n!").unwrap();
+    universe.set_topology(Topology::Bounded);
+    universe.load_rle(r_pentomino).unwrap();
+    let mut hashlife = HashLife::from_rle(&universe.to_rle()).unwrap();
+
+    for _ in 0..50 {
+        universe.tick();
+    }
+    hashlife.step(50).unwrap();
+    assert_eq!(hashlife.generation(), 50);
+
+    // HashLife only encodes the bounding box of the live cells
+    let expected = HashLife::from_rle(&universe.to_rle()).unwrap();
+    assert_eq!(expected.to_rle(), hashlife.to_rle());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_sparse_universe() {
+    use wasm_game_of_life::{BoundingBox, HashLife, SparseUniverse};
+
+    let mut glider = SparseUniverse::from_rle("bo$2bo$3o!").unwrap();
+    let start = glider.bounding_box().unwrap();
+    for _ in 0..40 {
+        glider.tick();
+    }
+
+    // Nothing to wrap around, the glider just keeps going
+    let moved = BoundingBox {
+        top: start.top + 10,
+        left: start.left + 10,
+        bottom: start.bottom + 10,
+        right: start.right + 10,
+    };
+    assert_eq!(glider.bounding_box(), Some(moved));
+    assert_eq!(glider.population(), 5);
+
+    glider.toggle_cell(-5, -5);
+    assert!(glider.is_alive(-5, -5));
+    glider.reset();
+    assert_eq!(glider.bounding_box(), None);
+
+    // Same result as HashLife for a pattern that grows in every direction
+    let r_pentomino = "b2o$2o$bo!";
+    let mut sparse = SparseUniverse::from_rle(r_pentomino).unwrap();
+    let mut hashlife = HashLife::from_rle(r_pentomino).unwrap();
+    for _ in 0..200 {
+        sparse.tick();
+    }
+    hashlife.step(200).unwrap();
+    assert_eq!(sparse.to_rle(), hashlife.to_rle());
+    let copy = SparseUniverse::from_rle(&sparse.to_rle()).unwrap();
+    assert_eq!(copy.population(), sparse.population());
+
+    // Far apart cells are encoded without a grid the size of the bounding box
+    let mut far = SparseUniverse::new();
+    far.set_cells(&[(0, 0), (0, 1), (200_000, 200_000), (i64::MIN, 3)]);
+    let rle = far.to_rle();
+    assert!(rle.starts_with("x = 200001, y = 9223372036854975809, rule = B3/S23\n"));
+    assert!(rle.ends_with("\n3bo9223372036854775808$2o200000$200000bo!\n"));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_randomize_with_seed() {
+    use wasm_game_of_life::{Cell, PackedUniverse};
+
+    let mut first = Universe::from_rle("x = 20, y = 20\n!").unwrap();
+    let mut second = Universe::from_rle("x = 20, y = 20\n!").unwrap();
+    first.randomize_with_seed(42, 0.3);
+    second.randomize_with_seed(42, 0.3);
+    assert_eq!(&first.get_cells(), &second.get_cells());
+    assert_eq!(first.seed(), 42);
+
+    let alive = first.get_cells().iter().filter(|&&cell| cell == Cell::Alive).count();
+    assert!(alive > 60 && alive < 180);
+
+    second.randomize_with_seed(43, 0.3);
+    assert_ne!(&first.get_cells(), &second.get_cells());
+
+    let mut packed = PackedUniverse::new(20, 20);
+    packed.randomize_with_seed(42, 0.3);
+    assert_eq!(&packed.to_cells()[..], first.get_cells());
+
+    first.randomize_with_seed(7, 1.0);
+    assert!(first.get_cells().iter().all(|&cell| cell == Cell::Alive));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_pluggable_logger() {
+    use std::sync::{Arc, Mutex};
+    use wasm_game_of_life::{set_logger, Logger, NullLogger};
+
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Logger for Recorder {
+        fn log(&self, message: &str) {
+            self.0.lock().unwrap().push(message.to_string());
+        }
+
+        fn time(&self, label: &str) {
+            self.0.lock().unwrap().push(format!("time {}", label));
+        }
+
+        fn time_end(&self, label: &str) {
+            self.0.lock().unwrap().push(format!("time_end {}", label));
+        }
+    }
+
+    let messages = Arc::new(Mutex::new(Vec::new()));
+    set_logger(Box::new(Recorder(messages.clone())));
+
+    let mut universe = Universe::new();
+    universe.tick();
+    // Other tests must not log into the recorder
+    set_logger(Box::new(NullLogger));
+
+    let messages = messages.lock().unwrap();
+    assert!(messages.contains(&"Init Universe from wasm".to_string()));
+    assert!(messages.contains(&"time Universe::tick".to_string()));
+    assert!(messages.contains(&"time_end Universe::tick".to_string()));
+
+    // A logger can replace itself while logging without a deadlock
+    struct Replacer;
+
+    impl Logger for Replacer {
+        fn log(&self, _message: &str) {
+            set_logger(Box::new(NullLogger));
+        }
+
+        fn time(&self, _label: &str) {}
+
+        fn time_end(&self, _label: &str) {}
+    }
+
+    set_logger(Box::new(Replacer));
+    Universe::new();
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_undo_redo() {
+    let mut universe = input_spaceship();
+    let start = universe.get_cells().to_vec();
+    universe.clear_history();
+    assert!(!universe.can_undo());
+
+    universe.tick();
+    universe.toggle_cell(0, 0);
+    let edited = universe.get_cells().to_vec();
+
+    // Undo the toggle, then the tick
+    assert!(universe.undo());
+    assert_eq!(&universe.get_cells(), &expected_spaceship().get_cells());
+    assert!(universe.undo());
+    assert_eq!(universe.get_cells(), &start[..]);
+    assert!(!universe.undo());
+
+    assert!(universe.redo());
+    assert!(universe.redo());
+    assert_eq!(universe.get_cells(), &edited[..]);
+    assert!(!universe.can_redo());
+
+    // A new edit drops whatever could be redone
+    universe.undo();
+    universe.reset();
+    assert!(!universe.can_redo());
+    assert!(universe.undo());
+    assert_eq!(&universe.get_cells(), &expected_spaceship().get_cells());
+
+    // With no room for any changes nothing can be undone
+    universe.set_history_limit(0);
+    universe.tick();
+    assert!(!universe.can_undo());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_step_back_and_seek() {
+    let mut universe = Universe::from_rle("x = 32, y = 32\n!").unwrap();
+    universe.randomize_with_seed(3, 0.4);
+
+    let mut generations = vec![universe.get_cells().to_vec()];
+    for _ in 0..100 {
+        universe.tick();
+        generations.push(universe.get_cells().to_vec());
+    }
+    assert_eq!(universe.generation(), 100);
+
+    assert!(universe.step_back());
+    assert_eq!(universe.generation(), 99);
+    assert_eq!(universe.get_cells(), &generations[99][..]);
+
+    // Back past several keyframes, then forward again
+    assert!(universe.seek(7));
+    assert_eq!(universe.get_cells(), &generations[7][..]);
+
+    // Seeking back is undoable like any other step
+    assert!(universe.undo());
+    assert_eq!(universe.generation(), 99);
+    assert_eq!(universe.get_cells(), &generations[99][..]);
+    assert!(universe.redo());
+    assert_eq!(universe.generation(), 7);
+
+    assert!(universe.seek(60));
+    assert_eq!(universe.get_cells(), &generations[60][..]);
+
+    // Only the last few generations are kept with a small capacity
+    universe.set_timeline_capacity(10);
+    universe.seek(100);
+    assert_eq!(universe.oldest_generation(), Some(90));
+    assert!(!universe.seek(50));
+    assert_eq!(universe.generation(), 100);
+    assert!(universe.seek(95));
+    assert_eq!(universe.get_cells(), &generations[95][..]);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_population_stats() {
+    // A blinker: two cells die and two are born every generation
+    let mut universe = Universe::from_rle("x = 5, y = 5\n5b$5b$b3o$5b$5b!").unwrap();
+    assert_eq!(universe.population(), 3);
+
+    universe.tick();
+    universe.tick();
+    assert_eq!(universe.generation(), 2);
+    assert_eq!(universe.population(), 3);
+    assert_eq!(universe.births(), 2);
+    assert_eq!(universe.deaths(), 2);
+
+    universe.toggle_cell(0, 0);
+    assert_eq!(universe.population_history(), vec![3, 3, 4]);
+
+    // Stepping back forgets the populations of later generations
+    universe.step_back();
+    assert_eq!(universe.population_history(), vec![3, 3]);
+
+    universe.set_population_history_capacity(1);
+    assert_eq!(universe.population_history(), vec![3]);
+    assert_eq!(universe.population_history_start(), 1);
+
+    universe.reset();
+    assert_eq!(universe.generation(), 0);
+    assert_eq!(universe.population(), 0);
+    assert_eq!(universe.population_history(), vec![0]);
+    assert_eq!(universe.population_history_start(), 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_cycle_detection() {
+    // A block is a still life from the start
+    let mut universe = Universe::from_rle("x = 6, y = 6\n6b$b2o$b2o!").unwrap();
+    universe.tick();
+    assert_eq!(universe.cycle(), None);
+    universe.set_detect_cycles(true);
+    universe.tick();
+    let cycle = universe.cycle().unwrap();
+    assert_eq!((cycle.start, cycle.period), (1, 1));
+    assert!(cycle.is_still_life());
+
+    // A blinker repeats every other generation, editing starts over
+    let mut universe = Universe::from_rle("x = 5, y = 5\n5b$5b$b3o!").unwrap();
+    universe.set_detect_cycles(true);
+    universe.tick();
+    assert_eq!(universe.cycle(), None);
+    universe.tick();
+    assert_eq!(universe.cycle().map(|cycle| cycle.period), Some(2));
+    universe.toggle_cell(0, 0);
+    assert_eq!(universe.cycle(), None);
+
+    // A glider only repeats itself exactly after crossing the whole torus,
+    // but moves a cell diagonally every four generations
+    let mut universe = Universe::from_rle("x = 10, y = 10\nbo$2bo$3o!").unwrap();
+    universe.set_detect_cycles(true);
+    universe.set_detect_spaceships(true);
+    for _ in 0..4 {
+        universe.tick();
+    }
+    let cycle = universe.cycle().unwrap();
+    assert_eq!((cycle.start, cycle.period, cycle.dx, cycle.dy), (0, 4, 1, 1));
+    assert!(cycle.is_spaceship());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_changed_cells() {
+    let mut universe = Universe::from_rle("x = 5, y = 5\n5b$5b$b3o!").unwrap();
+    assert!(universe.needs_redraw());
+    let before = universe.get_cells().to_vec();
+    universe.tick();
+
+    let changed: Vec<u32> = (0..before.len() as u32)
+        .filter(|&idx| before[idx as usize] != universe.get_cells()[idx as usize])
+        .collect();
+    assert_eq!(universe.get_changed_cells(), &changed[..]);
+    assert_eq!(universe.changed_cells_len(), 4);
+    assert!(!universe.needs_redraw());
+    assert!(Universe::new().needs_redraw());
+
+    // Edits, undo and resets report the cells they changed too
+    universe.reset();
+    assert_eq!(universe.get_changed_cells(), &[7, 12, 17]);
+    assert_eq!((universe.births(), universe.deaths()), (0, 3));
+    universe.toggle_cell(0, 0);
+    assert_eq!(universe.get_changed_cells(), &[0]);
+    assert_eq!((universe.births(), universe.deaths()), (1, 0));
+    assert!(universe.undo());
+    assert_eq!(universe.get_changed_cells(), &[0]);
+    assert_eq!((universe.births(), universe.deaths()), (0, 1));
+    universe.paste("2o!", 1, 1, wasm_game_of_life::PasteMode::Or).unwrap();
+    assert_eq!(universe.get_changed_cells(), &[6, 7]);
+
+    // Anything else asks for the whole universe to be drawn again
+    universe.resize(6, 6, wasm_game_of_life::Anchor::TopLeft).unwrap();
+    assert!(universe.needs_redraw());
+    assert_eq!(universe.changed_cells_len(), 0);
+    universe.tick();
+    assert!(!universe.needs_redraw());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_generations_rule() {
+    use wasm_game_of_life::{Cell, CellState, Rule};
+
+    let brians_brain: Rule = "B2/S/C3".parse().unwrap();
+    assert_eq!(brians_brain, Rule::new(&[2], &[]).with_states(3));
+    assert_eq!("/2/3".parse::<Rule>().unwrap(), brians_brain);
+    assert_eq!(brians_brain.to_string(), "B2/S/C3");
+    assert_eq!("23/3/2".parse::<Rule>().unwrap(), Rule::CONWAY);
+    assert!("B2/S/C1".parse::<Rule>().is_err());
+
+    // Live cells never survive in Brian's Brain, they die over two
+    // generations while the pair gives birth to the cells around it
+    let mut universe = Universe::from_rle("x = 6, y = 6, rule = B2/S/C3\n6.$6.$2.2A!").unwrap();
+    universe.tick();
+    assert_eq!(universe.get_cells()[2 * 6 + 2], Cell::from_state(2));
+    assert_eq!(universe.population(), 4);
+    assert_eq!(universe.deaths(), 2);
+    assert_eq!(
+        universe.to_rle(),
+        "x = 6, y = 6, rule = B2/S/C3\n$2.2A$2.2B$2.2A!\n"
+    );
+    universe.tick();
+    assert_eq!(universe.get_cells()[2 * 6 + 2], Cell::Dead);
+
+    // The states JavaScript sees are the bytes of the cells
+    assert_eq!(Cell::from(CellState::Dead), Cell::Dead);
+    assert_eq!(Cell::from(CellState::Alive).state(), 1);
+
+    // Dying states survive a round trip through RLE
+    let copy = Universe::from_rle(&universe.to_rle()).unwrap();
+    assert_eq!(copy.get_cells(), universe.get_cells());
+
+    use wasm_game_of_life::PackedUniverse;
+    assert!(PackedUniverse::from_rle("x = 1, y = 1, rule = B2/S/C3\nA!").is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_resize() {
+    use wasm_game_of_life::Anchor;
+
+    let mut universe = Universe::from_rle("x = 4, y = 4\n$b2o$b2o!").unwrap();
+    universe.tick();
+
+    // Growing around the center keeps the block in the middle
+    universe.resize(8, 6, Anchor::Center).unwrap();
+    assert_eq!((universe.width(), universe.height()), (8, 6));
+    assert_eq!(universe.to_rle(), "x = 8, y = 6, rule = B3/S23\n2$3b2o$3b2o!\n");
+    assert_eq!(universe.generation(), 1);
+
+    // Shrinking from the bottom right crops the top and left
+    universe.resize(5, 4, Anchor::BottomRight).unwrap();
+    assert_eq!(universe.to_rle(), "x = 5, y = 4, rule = B3/S23\n2o$2o!\n");
+    assert_eq!(universe.population(), 4);
+
+    universe.resize(2, 2, Anchor::BottomRight).unwrap();
+    assert!(universe.resize(70000, 70000, Anchor::Center).is_err());
+    assert_eq!((universe.width(), universe.height()), (2, 2));
+    assert_eq!(universe.population(), 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_checked_edits() {
+    use wasm_game_of_life::Error;
+
+    let mut universe = Universe::from_rle("x = 4, y = 3\n!").unwrap();
+    assert!(universe.try_toggle_cell(2, 3).is_ok());
+    assert_eq!(universe.population(), 1);
+
+    // A column past the edge must not wrap around to the next row
+    assert_eq!(
+        universe.try_toggle_cell(1, 4),
+        Err(Error::OutOfBounds {
+            row: 1,
+            column: 4,
+            width: 4,
+            height: 3
+        })
+    );
+
+    // Nothing is set if any cell is out of bounds
+    assert!(universe.try_set_cells(&[(0, 0), (3, 0)]).is_err());
+    assert_eq!(universe.population(), 1);
+    assert!(universe.try_set_cells(&[(0, 0), (0, 1)]).is_ok());
+    assert_eq!(universe.population(), 3);
+
+    let err: Error = Universe::from_rle("x = 1, y = 1, rule = B9/S\n!")
+        .err()
+        .unwrap()
+        .into();
+    assert!(err.to_string().starts_with("invalid RLE pattern"));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_pattern_formats() {
+    let glider = Universe::from_rle("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+
+    let plaintext = "!Name: Glider\n!\n.O\n..O\nOOO\n";
+    let universe = Universe::from_pattern(plaintext).unwrap();
+    assert_eq!(universe.get_cells(), glider.get_cells());
+    assert_eq!(universe.to_plaintext(), ".O.\n..O\nOOO\n");
+
+    // Life 1.06 coordinates are `x y` and may be negative
+    let life106 = "#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n";
+    let universe = Universe::from_pattern(life106).unwrap();
+    assert_eq!(universe.get_cells(), glider.get_cells());
+    assert_eq!(
+        universe.to_life106(),
+        "#Life 1.06\n1 0\n2 1\n0 2\n1 2\n2 2\n"
+    );
+
+    let life105 = "#Life 1.05\n#D Glider\n#R 23/36\n#P -1 -1\n.*\n..*\n***\n";
+    let universe = Universe::from_pattern(life105).unwrap();
+    assert_eq!(universe.get_cells(), glider.get_cells());
+    assert_eq!(universe.rule(), "B36/S23");
+    assert_eq!(
+        universe.to_life105(),
+        "#Life 1.05\n#R 23/36\n#P 0 0\n.*.\n..*\n***\n"
+    );
+
+    // RLE is still recognized, errors name the format they come from
+    let mut universe = Universe::from_pattern("#C glider\nx = 3, y = 3\nbo$2bo$3o!").unwrap();
+    assert_eq!(universe.get_cells(), glider.get_cells());
+    universe.load_pattern("O\nOO\n").unwrap();
+    assert_eq!(universe.population(), 3);
+    let err = universe.load_pattern("#Life 1.06\n1\n").unwrap_err();
+    assert!(err.to_string().starts_with("invalid Life 1.06 pattern"));
+    assert!(universe.load_pattern("OOOO\n").is_err());
+
+    // Cells too far apart are an error, not a panic
+    assert!(Universe::from_pattern("#Life 1.06\n0 0\n70000 70000").is_err());
+    let far = "#Life 1.06\n-9223372036854775808 0\n9223372036854775806 0";
+    assert!(Universe::from_pattern(far).is_err());
+    assert!(Universe::from_pattern("#Life 1.05\n#P 9223372036854775807 0\n**\n").is_err());
+    assert!(Universe::from_pattern("#Life 1.05\n#P 0 9223372036854775807\n*\n*\n").is_err());
+    let corner = "#Life 1.05\n#P 9223372036854775807 9223372036854775807\n*\n";
+    assert_eq!(Universe::from_pattern(corner).unwrap().population(), 1);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_macrocell() {
+    use wasm_game_of_life::HashLife;
+
+    let glider = "x = 3, y = 3\nbo$2bo$3o!";
+    let mut hashlife = HashLife::from_rle(glider).unwrap();
+
+    // The 8x8 root is centered on the origin, the glider is in its bottom
+    // right quarter
+    let mc = hashlife.to_macrocell();
+    assert_eq!(
+        mc,
+        "[M2] (wasm-game-of-life)\n#R B3/S23\n$$$$.....*$......*$....***$\n"
+    );
+    let copy = HashLife::from_macrocell(&mc).unwrap();
+    assert_eq!(copy.live_cells(), hashlife.live_cells());
+
+    // Far away from the origin the tree has many levels
+    hashlife.step(1 << 12).unwrap();
+    let copy = HashLife::from_macrocell(&hashlife.to_macrocell()).unwrap();
+    assert_eq!(copy.generation(), 1 << 12);
+    assert_eq!(copy.to_rle(), hashlife.to_rle());
+    assert!(copy.is_alive(1 << 10, (1 << 10) + 1));
+
+    // Small enough patterns also load into a Universe
+    let universe = Universe::from_pattern(&mc).unwrap();
+    assert_eq!(
+        universe.get_cells(),
+        Universe::from_rle(glider).unwrap().get_cells()
+    );
+
+    assert!(HashLife::from_macrocell("[M2]\n4 1 0 0 0\n").is_err());
+    assert!(HashLife::from_macrocell("x = 3, y = 3\n!").is_err());
+
+    // A universe writes its live cells with its top left corner at the origin
+    let mut universe = Universe::from_rle("x = 6, y = 6\nbo$2bo$3o!").unwrap();
+    universe.tick();
+    let copy = HashLife::from_macrocell(&universe.to_macrocell()).unwrap();
+    assert_eq!(copy.generation(), 1);
+    assert_eq!(copy.to_rle(), "x = 3, y = 3, rule = B3/S23\nobo$b2o$bo!\n");
+
+    // Cells far apart fit a u32 on each side but not in a universe
+    let mut far = HashLife::new();
+    far.set_cells(&[(0, 0), (131_072, 131_072)]).unwrap();
+    assert!(Universe::from_pattern(&far.to_macrocell()).is_err());
+
+    // Every level four copies of the one below, too many cells to list or
+    // even count
+    let mut dense = String::from("[M2]\n*\n");
+    for level in 4..40 {
+        dense.push_str(&format!("{} {n} {n} {n} {n}\n", level, n = level - 3));
+    }
+    assert_eq!(HashLife::from_macrocell(&dense).unwrap().population(), u64::MAX);
+    assert!(Universe::from_pattern(&dense).is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_pattern_catalog() {
+    use wasm_game_of_life::{find_pattern, patterns, Error, PatternKind, Transform};
+
+    // Oscillators and spaceships really have the period and speed the
+    // catalog claims
+    for info in patterns() {
+        let (kind, period) = match (info.kind(), info.period()) {
+            (PatternKind::Gun, _) | (_, None) => continue,
+            (kind, Some(period)) => (kind, period),
+        };
+        let mut universe = Universe::from_rle("x = 40, y = 40\n!").unwrap();
+        universe.insert_pattern(&info.name(), 15, 15, Transform::Identity).unwrap();
+        universe.set_detect_cycles(true);
+        universe.set_detect_spaceships(true);
+        for _ in 0..period {
+            universe.tick();
+        }
+
+        let cycle = universe.cycle().unwrap();
+        assert_eq!((cycle.start, cycle.period), (0, period), "{}", info.name());
+        assert_eq!(kind == PatternKind::StillLife, cycle.is_still_life());
+        let distance = cycle.dx.abs().max(cycle.dy.abs()) as u32;
+        let speed = period.checked_div(distance).map(|n| format!("c/{}", n));
+        assert_eq!(info.speed(), speed, "{}", info.name());
+    }
+
+    let gun = find_pattern("gosper-glider-gun").unwrap();
+    assert_eq!((gun.width(), gun.height()), (36, 9));
+
+    // A glider turned a quarter turn clockwise flies down and to the left
+    let mut universe = Universe::from_rle("x = 5, y = 5\n!").unwrap();
+    universe.insert_pattern("glider", 1, 1, Transform::Rotate90).unwrap();
+    assert_eq!(universe.to_rle(), "x = 5, y = 5, rule = B3/S23\n$bo$bobo$b2o!\n");
+
+    assert_eq!(
+        universe.insert_pattern("glider", 3, 3, Transform::Identity),
+        Err(Error::OutOfBounds {
+            row: 4,
+            column: 5,
+            width: 5,
+            height: 5
+        })
+    );
+    assert!(universe.insert_pattern("gun", 0, 0, Transform::Identity).is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_pattern_transforms() {
+    use wasm_game_of_life::{find_pattern, Anchor, Pattern, Topology, Transform};
+
+    let glider = Pattern::from_rle("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+    assert_eq!(find_pattern("glider").unwrap().to_pattern(), glider);
+    assert_eq!(glider.rotate(1), glider.transform(Transform::Rotate90));
+    assert_eq!(glider.rotate(-1), glider.rotate(3));
+    assert_eq!(glider.rotate(4), glider);
+    assert_eq!(glider.flip_horizontal().flip_horizontal(), glider);
+    assert_eq!(glider.flip_vertical().rotate(2), glider.flip_horizontal());
+    assert_eq!(glider.translate(2, -1).translate(-2, 1), glider);
+
+    let wide = Pattern::from_rle("x = 3, y = 1\n3o!").unwrap().rotate(1);
+    assert_eq!((wide.width(), wide.height()), (1, 3));
+
+    // The anchor picks the point of the pattern that goes at the position
+    let mut expected = Universe::from_rle("x = 5, y = 5\n!").unwrap();
+    expected.insert_pattern("glider", 1, 1, Transform::Identity).unwrap();
+    for &(row, column, anchor) in &[
+        (1, 1, Anchor::TopLeft),
+        (2, 2, Anchor::Center),
+        (3, 3, Anchor::BottomRight),
+    ] {
+        let mut universe = Universe::from_rle("x = 5, y = 5\n!").unwrap();
+        universe.set_pattern(&glider, row, column, anchor, Transform::Identity);
+        assert_eq!(universe.to_rle(), expected.to_rle());
+    }
+
+    let mut universe = Universe::from_rle("x = 5, y = 5\n!").unwrap();
+    universe.set_pattern(&glider.translate(1, 1), 0, 0, Anchor::TopLeft, Transform::Identity);
+    assert_eq!(universe.to_rle(), expected.to_rle());
+
+    // Cells past the edges wrap around a torus and are left out otherwise
+    let mut universe = Universe::from_rle("x = 5, y = 5\n!").unwrap();
+    universe.set_pattern(&glider, 4, 4, Anchor::TopLeft, Transform::Identity);
+    assert_eq!(universe.to_rle(), "x = 5, y = 5, rule = B3/S23\nbo$2o2bo3$o!\n");
+    assert_eq!(universe.population(), 5);
+
+    let mut universe = Universe::from_rle("x = 5, y = 5\n!").unwrap();
+    universe.set_topology(Topology::Bounded);
+    universe.set_pattern(&glider, 3, 3, Anchor::TopLeft, Transform::Identity);
+    assert_eq!(universe.to_rle(), "x = 5, y = 5, rule = B3/S23\n3$4bo!\n");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_clipboard() {
+    use wasm_game_of_life::{PasteMode, Selection};
+
+    let glider = "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n";
+    let mut universe = Universe::from_rle("x = 6, y = 6\nbo$2bo$3o!").unwrap();
+    assert_eq!(universe.copy(), None);
+
+    universe.select(4, 3, 10, 10);
+    let expected = Selection {
+        row: 4,
+        column: 3,
+        width: 3,
+        height: 2,
+    };
+    assert_eq!(universe.selection(), Some(expected));
+    universe.select(6, 0, 1, 1);
+    assert_eq!(universe.selection(), None);
+
+    universe.select(0, 0, 3, 3);
+    assert_eq!(universe.copy().as_deref(), Some(glider));
+    assert_eq!(universe.cut().as_deref(), Some(glider));
+    assert_eq!(universe.population(), 0);
+    assert!(universe.undo());
+    assert_eq!(universe.population(), 5);
+
+    // Or adds cells, Xor flips them and Overwrite replaces the whole box
+    universe.paste(glider, 3, 3, PasteMode::Or).unwrap();
+    assert_eq!(universe.population(), 10);
+    universe.paste(glider, 3, 3, PasteMode::Xor).unwrap();
+    assert_eq!(universe.population(), 5);
+    universe.paste("x = 2, y = 2\n$2o!", 0, 0, PasteMode::Overwrite).unwrap();
+    assert_eq!(universe.to_rle(), "x = 6, y = 6, rule = B3/S23\n$3o$3o!\n");
+    universe.paste("x = 2, y = 2\n2o$2o!", 0, 0, PasteMode::Xor).unwrap();
+    assert_eq!(universe.to_rle(), "x = 6, y = 6, rule = B3/S23\n2o$2bo$3o!\n");
+    assert!(universe.paste("x = 2, y = 2\n2q!", 0, 0, PasteMode::Or).is_err());
+
+    // Pasted cells wrap around the edges of a torus, like `set_pattern`
+    let mut universe = Universe::from_rle("x = 4, y = 4\n!").unwrap();
+    universe.paste("x = 2, y = 2\n2o$2o!", 3, 3, PasteMode::Or).unwrap();
+    assert_eq!(universe.to_rle(), "x = 4, y = 4, rule = B3/S23\no2bo3$o2bo!\n");
+    let far = "x = 100000, y = 100000\n2o$2o99998$99999bo!";
+    universe.paste(far, 2, 2, PasteMode::Overwrite).unwrap();
+    assert_eq!(universe.to_rle(), "x = 4, y = 4, rule = B3/S23\n$bo$2b2o$2b2o!\n");
+
+    // and are left out past edges that do not wrap
+    use wasm_game_of_life::Topology;
+    let mut universe = Universe::from_rle("x = 4, y = 4\n!").unwrap();
+    universe.set_topology(Topology::Bounded);
+    universe.paste(far, 2, 2, PasteMode::Overwrite).unwrap();
+    assert_eq!(universe.to_rle(), "x = 4, y = 4, rule = B3/S23\n2$2b2o$2b2o!\n");
+
+    universe.select_all();
+    universe.fill_selection();
+    assert_eq!(universe.population(), 16);
+    universe.erase_selection();
+    assert_eq!(universe.population(), 0);
+    universe.deselect();
+    assert_eq!(universe.selection(), None);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_drawing() {
+    use wasm_game_of_life::Cell;
+
+    let mut universe = Universe::from_rle("x = 6, y = 5\n!").unwrap();
+
+    universe.draw_line(0, 0, 2, 5, true);
+    assert_eq!(universe.to_rle(), "x = 6, y = 5, rule = B3/S23\n2o$2b2o$4b2o!\n");
+    universe.draw_line(4, 0, 0, 4, true);
+    assert_eq!(universe.to_rle(), "x = 6, y = 5, rule = B3/S23\n2o2bo$2b2o$2bob2o$bo$o!\n");
+    universe.draw_line(1, 0, 1, 9, false);
+    assert_eq!(universe.to_rle(), "x = 6, y = 5, rule = B3/S23\n2o2bo2$2bob2o$bo$o!\n");
+    assert!(universe.undo());
+    assert_eq!(universe.population(), 10);
+
+    // Lines are clipped before they are walked, so far ends cost nothing
+    let mut far = Universe::from_rle("x = 6, y = 5\n!").unwrap();
+    far.draw_line(0, 0, u32::MAX, u32::MAX, true);
+    far.draw_line(2, u32::MAX, 2, 0, true);
+    far.draw_line(u32::MAX, 0, 0, u32::MAX, true);
+    assert_eq!(far.to_rle(), "x = 6, y = 5, rule = B3/S23\no$bo$6o$3bo$4bo!\n");
+
+    // and a clipped line keeps the cells of the whole one
+    for &(row0, column0, row1, column1) in &[(3, 7, 250, 90), (280, 5, 2, 170), (90, 299, 10, 0)] {
+        let mut small = Universe::from_rle("x = 60, y = 50\n!").unwrap();
+        let mut large = Universe::from_rle("x = 300, y = 300\n!").unwrap();
+        small.draw_line(row0, column0, row1, column1, true);
+        large.draw_line(row0, column0, row1, column1, true);
+        for (row, cells) in small.get_cells().chunks(60).enumerate() {
+            assert_eq!(cells, &large.get_cells()[row * 300..row * 300 + 60]);
+        }
+    }
+
+    universe.clear_rect(0, 0, 10, 10);
+    assert_eq!(universe.population(), 0);
+    universe.fill_rect(3, 4, 10, 10);
+    assert_eq!(universe.to_rle(), "x = 6, y = 5, rule = B3/S23\n3$4b2o$4b2o!\n");
+    universe.clear_rect(4, 5, 1, 1);
+    assert_eq!(universe.population(), 3);
+
+    // A random fill only touches its rectangle and is the same for a seed
+    let mut universe = Universe::from_rle("x = 20, y = 20\n!").unwrap();
+    universe.fill_rect(0, 0, 20, 1);
+    universe.randomize_rect(5, 5, 10, 10, 0.5, 42);
+    let soup = universe.to_rle();
+    let population = universe.population();
+    assert!(population > 20 + 20 && population < 20 + 80);
+    let cells = universe.get_cells();
+    for (i, &cell) in cells.iter().enumerate() {
+        let (row, col) = (i / 20, i % 20);
+        let inside = row == 0 || (5..15).contains(&row) && (5..15).contains(&col);
+        if !inside {
+            assert_eq!(cell, Cell::Dead);
+        }
+    }
+    universe.randomize_rect(5, 5, 10, 10, 0.0, 42);
+    assert_eq!(universe.population(), 20);
+    universe.randomize_rect(5, 5, 10, 10, 0.5, 42);
+    assert_eq!(universe.to_rle(), soup);
+    assert_eq!(universe.generation(), 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_cell_ages() {
+    use wasm_game_of_life::Anchor;
+
+    // A blinker next to a block
+    let mut universe = Universe::from_rle("x = 12, y = 5\n7b2o$7b2o$b3o!").unwrap();
+    assert!(universe.ages().is_null());
+    assert_eq!(universe.get_ages(), None);
+
+    universe.set_track_ages(true);
+    assert!(universe.tracks_ages());
+    assert_eq!(universe.get_ages().unwrap(), &[0; 60][..]);
+    universe.tick();
+    universe.tick();
+
+    let ages = universe.get_ages().unwrap();
+    let activity = universe.get_activity().unwrap();
+    let index = |row: usize, col: usize| row * 12 + col;
+    assert_eq!(ages[index(0, 7)], 2);
+    assert_eq!(ages[index(2, 2)], 2);
+    assert_eq!((ages[index(2, 1)], activity[index(2, 1)]), (0, 2));
+    assert_eq!((ages[index(1, 2)], activity[index(1, 2)]), (0, 2));
+    assert_eq!(activity[index(2, 2)], 0);
+    universe.tick();
+    assert_eq!(universe.get_ages().unwrap()[index(1, 2)], 0);
+    assert_eq!(universe.get_activity().unwrap()[index(1, 2)], 3);
+
+    // Edits make cells newborn, a reset starts over
+    universe.toggle_cell(0, 7);
+    universe.toggle_cell(0, 7);
+    assert_eq!(universe.get_ages().unwrap()[index(0, 7)], 0);
+    assert_eq!(universe.get_activity().unwrap()[index(0, 7)], 2);
+    universe.reset();
+    assert!(universe.get_activity().unwrap().iter().all(|&count| count == 0));
+
+    universe.resize(10, 10, Anchor::TopLeft).unwrap();
+    assert_eq!(universe.get_ages().unwrap().len(), 100);
+    universe.set_track_ages(false);
+    assert!(universe.activity().is_null());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_renderer() {
+    use wasm_game_of_life::Renderer;
+
+    let (grid, dead, alive) = (0xcccc_ccff, 0xffff_ffff, 0x0000_00ff);
+    let universe = Universe::from_rle("x = 3, y = 2\nbo!").unwrap();
+    let mut renderer = Renderer::new(2, 1);
+    renderer.render(&universe).unwrap();
+    assert_eq!((renderer.width(), renderer.height()), (10, 7));
+    assert_eq!(renderer.get_pixels().len(), 10 * 7 * 4);
+    assert_eq!(renderer.pixel(0, 0), grid);
+    assert_eq!(renderer.pixel(1, 1), dead);
+    assert_eq!(renderer.pixel(4, 1), alive);
+    assert_eq!(renderer.pixel(5, 2), alive);
+    assert_eq!(renderer.pixel(6, 2), grid);
+    assert_eq!(renderer.pixel(4, 3), grid);
+    assert_eq!(renderer.pixel(4, 5), dead);
+
+    // Without a grid the cells touch
+    renderer.set_grid_width(0);
+    renderer.set_cell_size(1);
+    renderer.set_alive_color(0xff00_00ff);
+    renderer.render(&universe).unwrap();
+    assert_eq!((renderer.width(), renderer.height()), (3, 2));
+    assert_eq!(renderer.pixel(0, 0), dead);
+    assert_eq!(&renderer.get_pixels()[4..8], &[0xff, 0x00, 0x00, 0xff]);
+
+    // Dying cells fade from the alive color to the dead color
+    let universe = Universe::from_rle("x = 3, y = 1, rule = B2/S/C3\nAB!").unwrap();
+    let mut renderer = Renderer::default();
+    renderer.render(&universe).unwrap();
+    assert_eq!((renderer.width(), renderer.height()), (19, 7));
+    assert_eq!(renderer.pixel(1, 1), alive);
+    assert_eq!(renderer.pixel(7, 1), 0x7f7f_7fff);
+    assert_eq!(renderer.pixel(13, 1), dead);
+
+    // Images too big for a pixel buffer are an error and keep the last one
+    let mut huge = Renderer::new(u32::MAX, 1);
+    assert!(huge.render(&universe).is_err());
+    huge.set_cell_size(1);
+    huge.render(&universe).unwrap();
+    huge.set_cell_size(1 << 30);
+    assert!(huge.render(&universe).is_err());
+    assert_eq!((huge.width(), huge.height()), (7, 3));
+}